@@ -0,0 +1,241 @@
+use crate::prelude::{Direction, Grid, GridError, Location};
+use std::collections::HashSet;
+use std::convert::TryFrom;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Aim {
+    Direction(Direction),
+    Toward(Location),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Template {
+    Sphere { radius: u8 },
+    Cylinder { radius: u8, height: u8 },
+    Cube { size: u8, direction: Direction },
+    Cone { length: u8, aim: Aim },
+    Line { length: u8, width: u8, aim: Aim },
+}
+
+// A cell is inside a template when its center is, measured in squares from the
+// center of the origin cell. Cells without a clear line of sight from the origin
+// are cut off, so effects never leak through Full boundries or obstructions.
+pub fn area_of_effect(
+    grid: &Grid,
+    origin: &Location,
+    template: &Template,
+) -> Result<HashSet<Location>, GridError> {
+    grid.get_ref_cell(origin)?;
+
+    let mut cells = HashSet::new();
+
+    let (reach, layers) = template.reach();
+
+    for dx in -reach..=reach {
+        for dy in layers.0..=layers.1 {
+            for dz in -reach..=reach {
+                let loc = match (
+                    i8::try_from(origin.x as i32 + dx),
+                    i8::try_from(origin.y as i32 + dy),
+                    i8::try_from(origin.z as i32 + dz),
+                ) {
+                    (Ok(x), Ok(y), Ok(z)) => Location::new(x, y, z),
+                    _ => continue,
+                };
+
+                let is_open = grid
+                    .get_ref_cell(&loc)
+                    .is_ok_and(|cell| !cell.is_obstructed);
+
+                if is_open
+                    && template.contains(origin, (dx, dy, dz))
+                    && grid.has_line_of_sight(origin, &loc)?.0
+                {
+                    cells.insert(loc);
+                }
+            }
+        }
+    }
+
+    Ok(cells)
+}
+
+impl Template {
+    fn reach(&self) -> (i32, (i32, i32)) {
+        match *self {
+            Template::Sphere { radius } => (radius as i32, (-(radius as i32), radius as i32)),
+            Template::Cylinder { radius, height } => (radius as i32, (0, height as i32 - 1)),
+            Template::Cube { size, .. } => (size as i32, (0, size as i32 - 1)),
+            Template::Cone { length, .. } | Template::Line { length, .. } => {
+                (length as i32, (-(length as i32), length as i32))
+            }
+        }
+    }
+
+    fn contains(&self, origin: &Location, (dx, dy, dz): (i32, i32, i32)) -> bool {
+        match *self {
+            Template::Sphere { radius } => {
+                dx * dx + dy * dy + dz * dz <= (radius as i32) * (radius as i32)
+            }
+            Template::Cylinder { radius, height } => {
+                dx * dx + dz * dz <= (radius as i32) * (radius as i32)
+                    && dy >= 0
+                    && dy < height as i32
+            }
+            Template::Cube { size, direction } => {
+                let unit = direction.offset();
+                let size = size as i32;
+                let within = |offset: i32, unit: i32| match unit {
+                    0 => offset >= -(size - 1) / 2 && offset <= size / 2,
+                    _ => offset * unit >= 1 && offset * unit <= size,
+                };
+
+                within(dx, unit.x as i32) && within(dz, unit.z as i32) && dy >= 0 && dy < size
+            }
+            Template::Cone { length, aim } => match project(origin, &aim, (dx, dy, dz)) {
+                Some((along, across)) => {
+                    along > 0.0 && along <= length as f32 && across <= along / 2.0
+                }
+                None => false,
+            },
+            Template::Line { length, width, aim } => match project(origin, &aim, (dx, dy, dz)) {
+                Some((along, across)) => {
+                    along > 0.0 && along <= length as f32 && across <= width as f32 / 2.0
+                }
+                None => false,
+            },
+        }
+    }
+}
+
+// Splits an offset into its distance along the aim and its distance from the aim.
+fn project(origin: &Location, aim: &Aim, (dx, dy, dz): (i32, i32, i32)) -> Option<(f32, f32)> {
+    let axis = match *aim {
+        Aim::Direction(dir) => {
+            let offset = dir.offset();
+            (offset.x as f32, offset.y as f32, offset.z as f32)
+        }
+        Aim::Toward(loc) => (
+            (loc.x as i32 - origin.x as i32) as f32,
+            (loc.y as i32 - origin.y as i32) as f32,
+            (loc.z as i32 - origin.z as i32) as f32,
+        ),
+    };
+
+    let axis_len = (axis.0 * axis.0 + axis.1 * axis.1 + axis.2 * axis.2).sqrt();
+
+    if axis_len == 0.0 {
+        return None;
+    }
+
+    let (dx, dy, dz) = (dx as f32, dy as f32, dz as f32);
+    let along = (dx * axis.0 + dy * axis.1 + dz * axis.2) / axis_len;
+    let across_sq = (dx * dx + dy * dy + dz * dz) - along * along;
+
+    // Offsets lying exactly on a template edge can land a hair outside it.
+    Some((along, across_sq.max(0.0).sqrt() - 1e-4))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::Boundry;
+    use rstest::{fixture, rstest};
+
+    #[fixture]
+    fn grid() -> Grid {
+        Grid::new(9, 9, 3)
+    }
+
+    #[rstest]
+    #[case(Template::Sphere { radius: 1 }, 7)]
+    #[case(Template::Sphere { radius: 2 }, 31)]
+    #[case(Template::Cylinder { radius: 1, height: 2 }, 10)]
+    #[case(Template::Cube { size: 2, direction: Direction::E }, 8)]
+    #[case(Template::Cube { size: 2, direction: Direction::NE }, 8)]
+    #[case(Template::Cone { length: 3, aim: Aim::Direction(Direction::E) }, 15)]
+    #[case(Template::Line { length: 3, width: 1, aim: Aim::Direction(Direction::E) }, 3)]
+    #[case(Template::Line { length: 3, width: 1, aim: Aim::Toward(Location::new(4, 1, 4)) }, 0)]
+    fn template_sizes(grid: Grid, #[case] template: Template, #[case] expected: usize) {
+        let result = area_of_effect(&grid, &Location::new(4, 1, 4), &template).unwrap();
+        assert_eq!(result.len(), expected);
+    }
+
+    #[rstest]
+    fn cone_follows_target(grid: Grid) {
+        let template = Template::Cone {
+            length: 2,
+            aim: Aim::Toward(Location::new(4, 1, 0)),
+        };
+
+        let result = area_of_effect(&grid, &Location::new(4, 1, 4), &template).unwrap();
+        let expected = vec![
+            Location::new(4, 1, 3),
+            Location::new(4, 0, 2),
+            Location::new(4, 2, 2),
+            Location::new(3, 1, 2),
+            Location::new(4, 1, 2),
+            Location::new(5, 1, 2),
+        ]
+        .into_iter()
+        .collect::<HashSet<Location>>();
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn distant_targets_keep_their_aim() {
+        let grid = Grid::new(127, 127, 1);
+        let origin = Location::new(126, 0, 0);
+        let line = |target| Template::Line {
+            length: 10,
+            width: 1,
+            aim: Aim::Toward(target),
+        };
+
+        assert_eq!(
+            area_of_effect(&grid, &origin, &line(Location::new(-128, 0, 127))),
+            area_of_effect(&grid, &origin, &line(Location::new(-2, 0, 64)))
+        );
+    }
+
+    #[test]
+    fn templates_do_not_wrap_past_the_grid() {
+        let grid = Grid::new(127, 1, 1);
+        let template = Template::Cube {
+            size: 130,
+            direction: Direction::E,
+        };
+
+        let result = area_of_effect(&grid, &Location::new(126, 0, 0), &template).unwrap();
+        assert!(result.is_empty());
+    }
+
+    #[rstest]
+    fn sphere_stops_at_full_walls(mut grid: Grid) {
+        for z in 0..grid.width {
+            grid.add_cell_boundry_and_adjacent(
+                &Location::new(4, 1, z),
+                Direction::E,
+                Boundry::Full,
+            )
+            .unwrap();
+        }
+        grid.toggle_cell_obstruction(&Location::new(3, 1, 4))
+            .unwrap();
+
+        let result = area_of_effect(
+            &grid,
+            &Location::new(4, 1, 4),
+            &Template::Sphere { radius: 2 },
+        )
+        .unwrap();
+
+        assert!(result.contains(&Location::new(4, 1, 4)));
+        assert!(result.contains(&Location::new(4, 2, 5)));
+        assert!(!result.contains(&Location::new(5, 1, 4)));
+        assert!(!result.contains(&Location::new(3, 1, 4)));
+        assert!(!result.contains(&Location::new(2, 1, 4)));
+        assert!(result.contains(&Location::new(3, 1, 3)));
+    }
+}
@@ -0,0 +1,364 @@
+//! ASCII maps draw every `y` layer as its own block, separated by blank lines and
+//! starting with layer 0. Each block is drawn at double resolution: cells sit on
+//! odd rows and columns, and the characters between them are the edges and corners
+//! of those cells. Rows run along `z` and columns along `x`.
+//!
+//! ```text
+//! +-+-+ +
+//! |. # ~|
+//! + +=+ +
+//! |* .!.|
+//! +-+-+-+
+//! ```
+//!
+//! Cells: `.` floor, `~` difficult terrain, `%` water, `&` deep water, `^` lava,
+//! `_` ice, `:` rubble and `;` vegetation. `#` is an obstructed floor and `*` an
+//! obstructed cell of difficult terrain. Obstructed cells of other terrains are
+//! drawn as `#`, and custom terrains as `.`.
+//!
+//! Edges between cells: `|` and `-` are `Boundry::Full` walls, `!` and `=` are
+//! `Boundry::Half` walls, and a space means no wall.
+//!
+//! Corners: `+` or a space for none. Diagonal walls between the two pairs of cells
+//! touching a corner are drawn as `\` (north-west to south-east), `/` (north-east
+//! to south-west) or `X` (both) for Full walls, and `'`, `,` or `x` for Half walls.
+//! `Y` is a Full `\` wall crossing a Half `/` one and `y` the other way round.
+//!
+//! Boundries between layers, stairs and ramps, weights, terrain rules and the
+//! entities in cells are not part of the format.
+
+use crate::prelude::{Boundry, Cell, Direction, Grid, GridError, Location, Terrain};
+use std::convert::TryFrom;
+
+static TERRAINS: [(char, Terrain); 8] = [
+    ('.', Terrain::Floor),
+    ('~', Terrain::Difficult),
+    ('%', Terrain::Water),
+    ('&', Terrain::DeepWater),
+    ('^', Terrain::Lava),
+    ('_', Terrain::Ice),
+    (':', Terrain::Rubble),
+    (';', Terrain::Vegetation),
+];
+
+static CORNERS: [(char, Option<Boundry>, Option<Boundry>); 9] = [
+    ('+', None, None),
+    ('\\', Some(Boundry::Full), None),
+    ('/', None, Some(Boundry::Full)),
+    ('X', Some(Boundry::Full), Some(Boundry::Full)),
+    ('\'', Some(Boundry::Half), None),
+    (',', None, Some(Boundry::Half)),
+    ('x', Some(Boundry::Half), Some(Boundry::Half)),
+    ('Y', Some(Boundry::Full), Some(Boundry::Half)),
+    ('y', Some(Boundry::Half), Some(Boundry::Full)),
+];
+
+impl Grid {
+    pub fn from_ascii(text: &str) -> Result<Grid, GridError> {
+        let layers = ascii_layers(text);
+
+        let first = layers.first().ok_or(GridError::InvalidAscii(0, 0))?;
+        let rows = first.len();
+        let columns = first
+            .iter()
+            .map(|(_, line)| line.trim_end().chars().count())
+            .max()
+            .unwrap_or(0);
+
+        if rows < 3 || columns < 3 || rows % 2 == 0 || columns % 2 == 0 {
+            return Err(GridError::InvalidAscii(first[0].0, 0));
+        }
+
+        let (length, width, height) = match (
+            i8::try_from(columns / 2),
+            i8::try_from(rows / 2),
+            i8::try_from(layers.len()),
+        ) {
+            (Ok(length), Ok(width), Ok(height)) => (length, width, height),
+            _ => return Err(GridError::InvalidAscii(first[0].0, 0)),
+        };
+
+        let mut grid = Grid::new(length, width, height);
+
+        for (y, layer) in layers.iter().enumerate() {
+            if layer.len() != rows {
+                return Err(GridError::InvalidAscii(layer[0].0, 0));
+            }
+
+            for (row, (line_number, line)) in layer.iter().enumerate() {
+                let mut chars: Vec<char> = line.trim_end().chars().collect();
+
+                if chars.len() > columns {
+                    return Err(GridError::InvalidAscii(*line_number, columns + 1));
+                }
+
+                chars.resize(columns, ' ');
+
+                for (column, c) in chars.into_iter().enumerate() {
+                    grid.read_ascii_char(y as i8, row, column, c)
+                        .map_err(|_| GridError::InvalidAscii(*line_number, column + 1))?;
+                }
+            }
+        }
+
+        Ok(grid)
+    }
+
+    pub fn to_ascii(&self) -> String {
+        let mut layers = vec![];
+
+        for y in 0..self.height {
+            let mut lines = vec![];
+
+            for row in 0..=2 * self.width as usize {
+                let line: String = (0..=2 * self.length as usize)
+                    .map(|column| self.write_ascii_char(y, row, column))
+                    .collect();
+
+                lines.push(line.trim_end().to_string());
+            }
+
+            layers.push(lines.join("\n"));
+        }
+
+        layers.join("\n\n") + "\n"
+    }
+
+    fn read_ascii_char(
+        &mut self,
+        y: i8,
+        row: usize,
+        column: usize,
+        c: char,
+    ) -> Result<(), GridError> {
+        let (x, z) = ((column / 2) as i8, (row / 2) as i8);
+
+        match (column % 2 == 1, row % 2 == 1) {
+            (true, true) => {
+                let cell = self.get_mut_cell(&Location::new(x, y, z))?;
+
+                match c {
+                    '#' => cell.is_obstructed = true,
+                    '*' => {
+                        cell.is_obstructed = true;
+                        cell.terrain = Terrain::Difficult;
+                    }
+                    _ => {
+                        cell.terrain = TERRAINS
+                            .iter()
+                            .find(|(terrain, _)| *terrain == c)
+                            .map(|(_, terrain)| *terrain)
+                            .ok_or(GridError::InvalidAscii(row, column))?
+                    }
+                }
+            }
+            (false, true) => {
+                let boundry = match c {
+                    ' ' => None,
+                    '|' => Some(Boundry::Full),
+                    '!' => Some(Boundry::Half),
+                    _ => return Err(GridError::InvalidAscii(row, column)),
+                };
+
+                if let Some(boundry) = boundry {
+                    self.add_edge_boundry(Location::new(x, y, z), Direction::W, boundry)?;
+                }
+            }
+            (true, false) => {
+                let boundry = match c {
+                    ' ' => None,
+                    '-' => Some(Boundry::Full),
+                    '=' => Some(Boundry::Half),
+                    _ => return Err(GridError::InvalidAscii(row, column)),
+                };
+
+                if let Some(boundry) = boundry {
+                    self.add_edge_boundry(Location::new(x, y, z), Direction::N, boundry)?;
+                }
+            }
+            (false, false) => {
+                let (nwse, nesw) = match c {
+                    ' ' => (None, None),
+                    _ => CORNERS
+                        .iter()
+                        .find(|(corner, _, _)| *corner == c)
+                        .map(|(_, nwse, nesw)| (*nwse, *nesw))
+                        .ok_or(GridError::InvalidAscii(row, column))?,
+                };
+
+                if let Some(boundry) = nwse {
+                    self.add_edge_boundry(Location::new(x, y, z), Direction::NW, boundry)?;
+                }
+
+                if let Some(boundry) = nesw {
+                    self.add_edge_boundry(Location::new(x - 1, y, z), Direction::NE, boundry)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_ascii_char(&self, y: i8, row: usize, column: usize) -> char {
+        let (x, z) = ((column / 2) as i8, (row / 2) as i8);
+        let loc = Location::new(x, y, z);
+
+        match (column % 2 == 1, row % 2 == 1) {
+            (true, true) => match self.get_ref_cell(&loc) {
+                Ok(Cell {
+                    is_obstructed: true,
+                    terrain: Terrain::Difficult,
+                    ..
+                }) => '*',
+                Ok(Cell {
+                    is_obstructed: true,
+                    ..
+                }) => '#',
+                Ok(cell) => TERRAINS
+                    .iter()
+                    .find(|(_, terrain)| *terrain == cell.terrain)
+                    .map_or('.', |(c, _)| *c),
+                Err(_) => '.',
+            },
+            (false, true) => match self.ascii_boundry(&loc, Direction::W) {
+                Some(Boundry::Full) => '|',
+                Some(Boundry::Half) => '!',
+                None => ' ',
+            },
+            (true, false) => match self.ascii_boundry(&loc, Direction::N) {
+                Some(Boundry::Full) => '-',
+                Some(Boundry::Half) => '=',
+                None => ' ',
+            },
+            (false, false) => {
+                let nwse = self.ascii_boundry(&loc, Direction::NW);
+                let nesw = self.ascii_boundry(&Location::new(x - 1, y, z), Direction::NE);
+
+                CORNERS
+                    .iter()
+                    .find(|(_, corner_nwse, corner_nesw)| {
+                        *corner_nwse == nwse && *corner_nesw == nesw
+                    })
+                    .map_or('+', |(c, _, _)| *c)
+            }
+        }
+    }
+
+    // The strongest wall that either side reports, as cells outside the grid or
+    // built by hand may only hold their own half of it.
+    fn ascii_boundry(&self, loc: &Location, dir: Direction) -> Option<Boundry> {
+        let neighbor = loc.get_loc_from_dir(&dir);
+        let side = |from: &Location, to: &Location| {
+            self.get_ref_cell(from)
+                .ok()
+                .and_then(|cell| cell.get_boundry_to_neighbor(to).ok().flatten())
+        };
+
+        match (side(loc, &neighbor), side(&neighbor, loc)) {
+            (Some(Boundry::Full), _) | (_, Some(Boundry::Full)) => Some(Boundry::Full),
+            (Some(Boundry::Half), _) | (_, Some(Boundry::Half)) => Some(Boundry::Half),
+            _ => None,
+        }
+    }
+}
+
+// Splits the text into layers of numbered lines, dropping blank lines around them.
+fn ascii_layers(text: &str) -> Vec<Vec<(usize, &str)>> {
+    let mut layers = vec![];
+    let mut layer = vec![];
+
+    for (idx, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            if !layer.is_empty() {
+                layers.push(layer);
+                layer = vec![];
+            }
+        } else {
+            layer.push((idx + 1, line));
+        }
+    }
+
+    if !layer.is_empty() {
+        layers.push(layer);
+    }
+
+    layers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    const MAP: &str = "\
++-+-+-+-+
+|. . # .|
++ +=+ + +
+|~ .!. *|
++ + / \\ +
+|. .|. .|
++-+-+-+-+
+
++ + + + +
+ . . ^ _
++ + + + +
+ : ; . .
++ + + + +
+ . % & .
++ + + + +
+";
+
+    #[test]
+    fn ascii_round_trip() {
+        let grid = Grid::from_ascii(MAP).unwrap();
+        assert_eq!(grid.to_ascii(), MAP);
+    }
+
+    #[test]
+    fn ascii_dimensions() {
+        let grid = Grid::from_ascii(MAP).unwrap();
+        assert_eq!((grid.length, grid.width, grid.height), (4, 3, 2));
+    }
+
+    #[rstest]
+    #[case(&Location::new(2, 0, 0), true, Terrain::Floor)]
+    #[case(&Location::new(0, 0, 1), false, Terrain::Difficult)]
+    #[case(&Location::new(3, 0, 1), true, Terrain::Difficult)]
+    #[case(&Location::new(1, 1, 1), false, Terrain::Vegetation)]
+    #[case(&Location::new(1, 1, 2), false, Terrain::Water)]
+    #[case(&Location::new(2, 1, 2), false, Terrain::DeepWater)]
+    fn ascii_cells(#[case] loc: &Location, #[case] obstructed: bool, #[case] terrain: Terrain) {
+        let grid = Grid::from_ascii(MAP).unwrap();
+        let cell = grid.get_ref_cell(loc).unwrap();
+
+        assert_eq!(cell.is_obstructed, obstructed);
+        assert_eq!(cell.terrain, terrain);
+    }
+
+    #[rstest]
+    #[case(&Location::new(1, 0, 0), &Location::new(1, 0, 1), Some(Boundry::Half))]
+    #[case(&Location::new(1, 0, 1), &Location::new(2, 0, 1), Some(Boundry::Half))]
+    #[case(&Location::new(1, 0, 2), &Location::new(2, 0, 2), Some(Boundry::Full))]
+    #[case(&Location::new(2, 0, 1), &Location::new(1, 0, 2), Some(Boundry::Full))]
+    #[case(&Location::new(2, 0, 1), &Location::new(3, 0, 2), Some(Boundry::Full))]
+    #[case(&Location::new(0, 0, 0), &Location::new(1, 0, 0), None)]
+    fn ascii_boundries(
+        #[case] from: &Location,
+        #[case] to: &Location,
+        #[case] expected: Option<Boundry>,
+    ) {
+        let grid = Grid::from_ascii(MAP).unwrap();
+        assert_eq!(grid.boundry_between(from, to), Ok(expected));
+    }
+
+    #[rstest]
+    #[case("", GridError::InvalidAscii(0, 0))]
+    #[case("+ +\n .\n+ +\n+ +", GridError::InvalidAscii(1, 0))]
+    #[case("+ +\n ?\n+ +", GridError::InvalidAscii(2, 2))]
+    #[case("+ +\n .\n+ +\n\n+ +\n .\n+ +\n .\n+ +", GridError::InvalidAscii(5, 0))]
+    #[case(&format!("{0}\n{1}\n{0}", "+ ".repeat(129), " .".repeat(128)), GridError::InvalidAscii(1, 0))]
+    #[case(&"+ +\n .\n+ +\n\n".repeat(128), GridError::InvalidAscii(1, 0))]
+    fn invalid_ascii(#[case] text: &str, #[case] expected: GridError) {
+        assert_eq!(Grid::from_ascii(text), Err(expected));
+    }
+}
@@ -0,0 +1,665 @@
+use crate::cost::is_valid_weight;
+use crate::prelude::{
+    Boundry, Connector, ConnectorKind, Direction, Grid, Location, MovementMode, Offset, Terrain,
+    TerrainRules,
+};
+use std::{
+    convert::{TryFrom, TryInto},
+    error::Error,
+    fmt,
+};
+
+// Layout of version 1, all integers little endian:
+//
+//   magic "PGRD", version u16, length, width and height as one byte each
+//   cell runs: a varint of `run_length << 1 | obstructed` followed by a varint
+//     terrain code (see `encode_terrain`), covering every cell in y, z, x order
+//   boundries: a varint count, then per boundry the varint index of its cell and
+//     one byte holding the direction's offset in base 3 shifted left once, with
+//     the low bit set for Half walls
+//   weights: a varint count, then per weight the varint index of its cell, one
+//     byte holding the edge's direction as above or `CELL_WEIGHT` for the cell's
+//     own weight, and the weight as an f64
+//   connectors: a varint count, then per connector the varint index of its cell,
+//     one byte holding its direction shifted left once, with the low bit set for
+//     ramps, and its cost as a varint
+//   terrains: a varint count, then per terrain whose rules differ from the built
+//     in ones its terrain code, a varint count of costs each as one byte holding
+//     the mode's index in `MovementMode::all` and a varint cost, one byte with a
+//     bit set for each mode it stops, and a varint count of tags each as a varint
+//     length and that many bytes of UTF-8
+//   crc32 of everything before it
+//
+// Entities are runtime state and are not saved. Readers keep a branch for every
+// version they have shipped, so saves from older versions keep loading.
+const MAGIC: &[u8; 4] = b"PGRD";
+const VERSION: u16 = 1;
+const HEADER_LEN: usize = 9;
+
+const CELL_WEIGHT: u8 = 0xff;
+
+const TERRAINS: [Terrain; 8] = [
+    Terrain::Floor,
+    Terrain::Difficult,
+    Terrain::Water,
+    Terrain::DeepWater,
+    Terrain::Lava,
+    Terrain::Ice,
+    Terrain::Rubble,
+    Terrain::Vegetation,
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BinaryError {
+    InvalidHeader,
+    UnsupportedVersion(u16),
+    ChecksumMismatch { expected: u32, found: u32 },
+    Truncated,
+    Corrupt,
+}
+
+impl fmt::Display for BinaryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BinaryError::InvalidHeader => write!(f, "not a binary grid"),
+            BinaryError::UnsupportedVersion(version) => {
+                write!(f, "binary grid version {} is not supported", version)
+            }
+            BinaryError::ChecksumMismatch { expected, found } => write!(
+                f,
+                "binary grid checksum is {:08x} but the data hashes to {:08x}",
+                expected, found
+            ),
+            BinaryError::Truncated => write!(f, "binary grid ends early"),
+            BinaryError::Corrupt => write!(f, "binary grid is corrupt"),
+        }
+    }
+}
+
+impl Error for BinaryError {}
+
+impl Grid {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&[self.length as u8, self.width as u8, self.height as u8]);
+
+        let mut boundries = vec![];
+        let mut weights = vec![];
+        let mut connectors = vec![];
+        let mut run: Option<((bool, Terrain), u64)> = None;
+
+        for (idx, loc) in self.binary_locations().enumerate() {
+            let cell = self.get_ref_cell(&loc).unwrap();
+            let key = (cell.is_obstructed, cell.terrain);
+
+            run = match run {
+                Some((run_key, count)) if run_key == key => Some((key, count + 1)),
+                Some((run_key, count)) => {
+                    write_run(&mut bytes, run_key, count);
+                    Some((key, 1))
+                }
+                None => Some((key, 1)),
+            };
+
+            let mut cell_boundries = cell
+                .boundries
+                .iter()
+                .flatten()
+                .map(|(dir, boundry)| (idx as u64, encode_boundry(dir, boundry)))
+                .collect::<Vec<(u64, u8)>>();
+
+            cell_boundries.sort_unstable();
+            boundries.extend(cell_boundries);
+
+            let mut cell_weights = cell
+                .edge_weights
+                .iter()
+                .flatten()
+                .map(|(dir, weight)| (idx as u64, encode_direction(dir), *weight))
+                .chain(cell.weight.map(|weight| (idx as u64, CELL_WEIGHT, weight)))
+                .collect::<Vec<(u64, u8, f64)>>();
+
+            cell_weights.sort_unstable_by_key(|(idx, kind, _)| (*idx, *kind));
+            weights.extend(cell_weights);
+
+            let mut cell_connectors = cell
+                .connectors
+                .iter()
+                .flatten()
+                .map(|(dir, connector)| {
+                    let kind = (connector.kind == ConnectorKind::Ramp) as u8;
+                    (
+                        idx as u64,
+                        encode_direction(dir) << 1 | kind,
+                        connector.cost,
+                    )
+                })
+                .collect::<Vec<(u64, u8, usize)>>();
+
+            cell_connectors.sort_unstable();
+            connectors.extend(cell_connectors);
+        }
+
+        if let Some((key, count)) = run {
+            write_run(&mut bytes, key, count);
+        }
+
+        write_varint(&mut bytes, boundries.len() as u64);
+
+        for (idx, boundry) in boundries.into_iter() {
+            write_varint(&mut bytes, idx);
+            bytes.push(boundry);
+        }
+
+        write_varint(&mut bytes, weights.len() as u64);
+
+        for (idx, kind, weight) in weights.into_iter() {
+            write_varint(&mut bytes, idx);
+            bytes.push(kind);
+            bytes.extend_from_slice(&weight.to_le_bytes());
+        }
+
+        write_varint(&mut bytes, connectors.len() as u64);
+
+        for (idx, byte, cost) in connectors.into_iter() {
+            write_varint(&mut bytes, idx);
+            bytes.push(byte);
+            write_varint(&mut bytes, cost as u64);
+        }
+
+        let terrains = self.terrain_table().overrides();
+        write_varint(&mut bytes, terrains.len() as u64);
+
+        for (terrain, rules) in terrains.into_iter() {
+            write_varint(&mut bytes, encode_terrain(&terrain));
+            write_rules(&mut bytes, rules);
+        }
+
+        let checksum = crc32(&bytes);
+        bytes.extend_from_slice(&checksum.to_le_bytes());
+
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Grid, BinaryError> {
+        if bytes.len() < MAGIC.len() + 2 || &bytes[..MAGIC.len()] != MAGIC {
+            return Err(BinaryError::InvalidHeader);
+        }
+
+        match u16::from_le_bytes([bytes[4], bytes[5]]) {
+            1 => {}
+            version => return Err(BinaryError::UnsupportedVersion(version)),
+        }
+
+        if bytes.len() < HEADER_LEN + 4 {
+            return Err(BinaryError::Truncated);
+        }
+
+        let (data, checksum) = bytes.split_at(bytes.len() - 4);
+        let expected = u32::from_le_bytes([checksum[0], checksum[1], checksum[2], checksum[3]]);
+        let found = crc32(data);
+
+        if expected != found {
+            return Err(BinaryError::ChecksumMismatch { expected, found });
+        }
+
+        read_v1(data)
+    }
+
+    fn binary_locations(&self) -> impl Iterator<Item = Location> {
+        let (length, width, height) = (self.length, self.width, self.height);
+
+        (0..height).flat_map(move |y| {
+            (0..width).flat_map(move |z| (0..length).map(move |x| Location::new(x, y, z)))
+        })
+    }
+}
+
+fn read_v1(data: &[u8]) -> Result<Grid, BinaryError> {
+    let mut reader = Reader {
+        bytes: data,
+        pos: HEADER_LEN,
+    };
+
+    let (length, width, height) = (data[6] as i8, data[7] as i8, data[8] as i8);
+
+    if length < 0 || width < 0 || height < 0 {
+        return Err(BinaryError::InvalidHeader);
+    }
+
+    let mut grid = Grid::new(length, width, height);
+    let locations = grid.binary_locations().collect::<Vec<Location>>();
+
+    let mut idx = 0;
+
+    while idx < locations.len() {
+        let run = reader.varint()?;
+
+        let (count, is_obstructed) = ((run >> 1) as usize, run & 1 != 0);
+        let terrain = decode_terrain(reader.varint()?)?;
+
+        if count == 0 || count > locations.len() - idx {
+            return Err(BinaryError::Corrupt);
+        }
+
+        for loc in locations[idx..idx + count].iter() {
+            let cell = grid.get_mut_cell(loc).unwrap();
+            cell.is_obstructed = is_obstructed;
+            cell.terrain = terrain;
+        }
+
+        idx += count;
+    }
+
+    for _ in 0..reader.varint()? {
+        let loc = locations
+            .get(reader.varint()? as usize)
+            .ok_or(BinaryError::Corrupt)?;
+        let (dir, boundry) = decode_boundry(reader.byte()?)?;
+
+        grid.get_mut_cell(loc).unwrap().add_boundry(dir, boundry);
+    }
+
+    for _ in 0..reader.varint()? {
+        let loc = locations
+            .get(reader.varint()? as usize)
+            .ok_or(BinaryError::Corrupt)?;
+        let kind = reader.byte()?;
+        let weight = reader.f64()?;
+
+        if !is_valid_weight(weight) {
+            return Err(BinaryError::Corrupt);
+        }
+
+        let cell = grid.get_mut_cell(loc).unwrap();
+
+        match kind {
+            CELL_WEIGHT => cell.weight = Some(weight),
+            kind => cell.set_edge_weight(decode_direction(kind)?, Some(weight)),
+        }
+    }
+
+    for _ in 0..reader.varint()? {
+        let loc = locations
+            .get(reader.varint()? as usize)
+            .ok_or(BinaryError::Corrupt)?;
+        let byte = reader.byte()?;
+        let dir = decode_direction(byte >> 1)?;
+
+        let connector = Connector {
+            kind: match byte & 1 {
+                0 => ConnectorKind::Stairs,
+                _ => ConnectorKind::Ramp,
+            },
+            cost: reader.varint()? as usize,
+        };
+
+        grid.get_mut_cell(loc)
+            .unwrap()
+            .add_connector(dir, connector)
+            .map_err(|_| BinaryError::Corrupt)?;
+    }
+
+    for _ in 0..reader.varint()? {
+        let terrain = decode_terrain(reader.varint()?)?;
+        let rules = read_rules(&mut reader)?;
+
+        grid.define_terrain(terrain, rules);
+    }
+
+    if reader.pos != data.len() {
+        return Err(BinaryError::Corrupt);
+    }
+
+    Ok(grid)
+}
+
+fn write_rules(bytes: &mut Vec<u8>, rules: &TerrainRules) {
+    let mut costs = rules
+        .costs
+        .iter()
+        .map(|(mode, cost)| (encode_mode(mode), *cost))
+        .collect::<Vec<(u8, usize)>>();
+    costs.sort_unstable();
+
+    write_varint(bytes, costs.len() as u64);
+
+    for (mode, cost) in costs.into_iter() {
+        bytes.push(mode);
+        write_varint(bytes, cost as u64);
+    }
+
+    bytes.push(
+        rules
+            .stops
+            .iter()
+            .fold(0, |stops, mode| stops | 1 << encode_mode(mode)),
+    );
+
+    let mut tags = rules.tags.iter().collect::<Vec<&String>>();
+    tags.sort_unstable();
+
+    write_varint(bytes, tags.len() as u64);
+
+    for tag in tags.into_iter() {
+        write_varint(bytes, tag.len() as u64);
+        bytes.extend_from_slice(tag.as_bytes());
+    }
+}
+
+fn read_rules(reader: &mut Reader) -> Result<TerrainRules, BinaryError> {
+    let mut rules = TerrainRules::default();
+
+    for _ in 0..reader.varint()? {
+        let mode = decode_mode(reader.byte()?)?;
+        rules.costs.insert(mode, reader.varint()? as usize);
+    }
+
+    let stops = reader.byte()?;
+    rules.stops = MovementMode::all()
+        .filter(|mode| stops & 1 << encode_mode(mode) != 0)
+        .collect();
+
+    if stops >> MovementMode::all().count() != 0 {
+        return Err(BinaryError::Corrupt);
+    }
+
+    for _ in 0..reader.varint()? {
+        let len = reader.varint()? as usize;
+        let tag = std::str::from_utf8(reader.take(len)?).map_err(|_| BinaryError::Corrupt)?;
+        rules.tags.insert(tag.to_string());
+    }
+
+    Ok(rules)
+}
+
+fn write_run(bytes: &mut Vec<u8>, (is_obstructed, terrain): (bool, Terrain), count: u64) {
+    write_varint(bytes, count << 1 | is_obstructed as u64);
+    write_varint(bytes, encode_terrain(&terrain));
+}
+
+// Built in terrains in declaration order, then custom ones after them.
+fn encode_terrain(terrain: &Terrain) -> u64 {
+    match terrain {
+        Terrain::Custom(id) => TERRAINS.len() as u64 + *id as u64,
+        _ => TERRAINS.iter().position(|other| other == terrain).unwrap() as u64,
+    }
+}
+
+fn decode_terrain(code: u64) -> Result<Terrain, BinaryError> {
+    match TERRAINS.get(code as usize) {
+        Some(terrain) => Ok(*terrain),
+        None => u16::try_from(code - TERRAINS.len() as u64)
+            .map(Terrain::Custom)
+            .map_err(|_| BinaryError::Corrupt),
+    }
+}
+
+fn encode_boundry(dir: &Direction, boundry: &Boundry) -> u8 {
+    encode_direction(dir) << 1 | (*boundry == Boundry::Half) as u8
+}
+
+fn decode_boundry(byte: u8) -> Result<(Direction, Boundry), BinaryError> {
+    let dir = decode_direction(byte >> 1)?;
+
+    let boundry = match byte & 1 {
+        0 => Boundry::Full,
+        _ => Boundry::Half,
+    };
+
+    Ok((dir, boundry))
+}
+
+fn encode_mode(mode: &MovementMode) -> u8 {
+    MovementMode::all()
+        .position(|other| other == *mode)
+        .unwrap() as u8
+}
+
+fn decode_mode(mode_idx: u8) -> Result<MovementMode, BinaryError> {
+    MovementMode::all()
+        .nth(mode_idx as usize)
+        .ok_or(BinaryError::Corrupt)
+}
+
+// A direction's offset in base 3.
+fn encode_direction(dir: &Direction) -> u8 {
+    let offset = dir.offset();
+
+    ((offset.x + 1) * 9 + (offset.y + 1) * 3 + (offset.z + 1)) as u8
+}
+
+fn decode_direction(dir_idx: u8) -> Result<Direction, BinaryError> {
+    if dir_idx >= 27 {
+        return Err(BinaryError::Corrupt);
+    }
+
+    let dir_idx = dir_idx as i8;
+    let offset = Offset::new(dir_idx / 9 - 1, dir_idx / 3 % 3 - 1, dir_idx % 3 - 1);
+
+    Direction::from_offset(&offset).ok_or(BinaryError::Corrupt)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn byte(&mut self) -> Result<u8, BinaryError> {
+        let byte = *self.bytes.get(self.pos).ok_or(BinaryError::Truncated)?;
+        self.pos += 1;
+
+        Ok(byte)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], BinaryError> {
+        let end = self.pos.checked_add(len).ok_or(BinaryError::Truncated)?;
+        let bytes = self
+            .bytes
+            .get(self.pos..end)
+            .ok_or(BinaryError::Truncated)?;
+        self.pos = end;
+
+        Ok(bytes)
+    }
+
+    fn f64(&mut self) -> Result<f64, BinaryError> {
+        Ok(f64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn varint(&mut self) -> Result<u64, BinaryError> {
+        let mut value = 0;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err(BinaryError::Corrupt)
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+
+    bytes.push(value as u8);
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+
+    for byte in bytes.iter() {
+        crc ^= *byte as u32;
+
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::{fixture, rstest};
+
+    #[fixture]
+    fn grid() -> Grid {
+        let mut grid = Grid::from_ascii(
+            "\
++-+-+-+-+
+|. . # .|
++ +=+ + +
+|~ .!. *|
++ + / \\ +
+|. .|. .|
++-+-+-+-+
+
++ + + + +
+ . . . .
++ + + + +
+ . ~ ~ .
++ + + + +
+ % & ^ .
++ + + + +
+",
+        )
+        .unwrap();
+
+        grid.get_mut_cell(&Location::new(1, 0, 1))
+            .unwrap()
+            .add_boundry(Direction::U, Boundry::Half);
+        grid.set_cell_terrain(&Location::new(3, 1, 0), Terrain::Custom(300))
+            .unwrap();
+        grid.add_cell_connector_and_adjacent(
+            &Location::new(0, 0, 0),
+            Direction::U,
+            Connector::stairs(2),
+        )
+        .unwrap();
+        grid.add_cell_connector_and_adjacent(
+            &Location::new(3, 1, 2),
+            Direction::DW,
+            Connector::ramp(1),
+        )
+        .unwrap();
+        grid.set_cell_weight(&Location::new(1, 1, 1), Some(1.5))
+            .unwrap();
+        grid.set_edge_weight(&Location::new(0, 0, 2), Direction::NE, Some(0.25))
+            .unwrap();
+        grid.define_terrain(
+            Terrain::Custom(300),
+            TerrainRules {
+                costs: vec![(MovementMode::Walk, 3), (MovementMode::Swim, 1)]
+                    .into_iter()
+                    .collect(),
+                stops: vec![MovementMode::Walk].into_iter().collect(),
+                tags: vec!["mud".to_string(), "sticky".to_string()]
+                    .into_iter()
+                    .collect(),
+            },
+        );
+        grid.define_terrain(Terrain::Lava, TerrainRules::default());
+
+        grid
+    }
+
+    #[rstest]
+    fn binary_round_trip(grid: Grid) {
+        assert_eq!(Grid::from_bytes(&grid.to_bytes()), Ok(grid));
+    }
+
+    #[rstest]
+    fn terrain_rules_round_trip(grid: Grid) {
+        let loaded = Grid::from_bytes(&grid.to_bytes()).unwrap();
+
+        assert_eq!(loaded.terrain_table(), grid.terrain_table());
+        assert_eq!(
+            loaded
+                .terrain_rules(&Location::new(3, 1, 0))
+                .unwrap()
+                .cost(MovementMode::Walk),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn empty_regions_stay_small() {
+        let grid = Grid::new(100, 100, 10);
+        let bytes = grid.to_bytes();
+
+        assert!(bytes.len() < 24);
+        assert_eq!(Grid::from_bytes(&bytes), Ok(grid));
+    }
+
+    #[rstest]
+    fn checksum_mismatch(grid: Grid) {
+        let mut bytes = grid.to_bytes();
+        bytes[HEADER_LEN] ^= 1;
+
+        assert!(matches!(
+            Grid::from_bytes(&bytes),
+            Err(BinaryError::ChecksumMismatch { .. })
+        ));
+    }
+
+    #[rstest]
+    #[case(-1.0)]
+    #[case(f64::NAN)]
+    #[case(f64::INFINITY)]
+    fn invalid_weights_are_corrupt(#[case] weight: f64) {
+        let mut grid = Grid::new(1, 1, 1);
+        grid.set_cell_weight(&Location::new(0, 0, 0), Some(1.0))
+            .unwrap();
+
+        let mut bytes = grid.to_bytes();
+        let end = bytes.len() - 6;
+        bytes[end - 8..end].copy_from_slice(&weight.to_le_bytes());
+
+        let data_len = bytes.len() - 4;
+        let checksum = crc32(&bytes[..data_len]);
+        bytes[data_len..].copy_from_slice(&checksum.to_le_bytes());
+
+        assert_eq!(Grid::from_bytes(&bytes), Err(BinaryError::Corrupt));
+    }
+
+    #[test]
+    fn horizontal_connectors_are_corrupt() {
+        let mut grid = Grid::new(1, 1, 2);
+        grid.add_cell_connector_and_adjacent(
+            &Location::new(0, 0, 0),
+            Direction::U,
+            Connector::stairs(1),
+        )
+        .unwrap();
+
+        let mut bytes = grid.to_bytes();
+        let end = bytes.len() - 6;
+        bytes[end - 1] = encode_direction(&Direction::E) << 1;
+
+        let data_len = bytes.len() - 4;
+        let checksum = crc32(&bytes[..data_len]);
+        bytes[data_len..].copy_from_slice(&checksum.to_le_bytes());
+
+        assert_eq!(Grid::from_bytes(&bytes), Err(BinaryError::Corrupt));
+    }
+
+    #[rstest]
+    #[case(b"PGRD".to_vec(), BinaryError::InvalidHeader)]
+    #[case(b"JSON\x01\x00\x01\x01\x01".to_vec(), BinaryError::InvalidHeader)]
+    #[case(b"PGRD\x02\x00\x01\x01\x01".to_vec(), BinaryError::UnsupportedVersion(2))]
+    #[case(b"PGRD\x01\x00\x01\x01\x01".to_vec(), BinaryError::Truncated)]
+    fn invalid_bytes(#[case] bytes: Vec<u8>, #[case] expected: BinaryError) {
+        assert_eq!(Grid::from_bytes(&bytes), Err(expected));
+    }
+}
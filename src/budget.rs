@@ -0,0 +1,167 @@
+use crate::prelude::{path_2d, Grid, GridError, Location};
+use std::collections::HashSet;
+
+// Movement for one creature over one turn. Each dash adds the creature's speed
+// again, after modifiers. Halved speed halves every allotment, and prone creatures
+// crawl at double cost until they spend half their speed to stand up.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MovementBudget {
+    pub speed: usize,
+    pub spent: usize,
+    pub dashes: usize,
+    pub halved: bool,
+    pub prone: bool,
+}
+
+impl MovementBudget {
+    pub fn new(speed: usize) -> Self {
+        MovementBudget {
+            speed,
+            ..MovementBudget::default()
+        }
+    }
+
+    pub fn effective_speed(&self) -> usize {
+        match self.halved {
+            true => self.speed / 2,
+            false => self.speed,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.effective_speed() * (1 + self.dashes)
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.total().saturating_sub(self.spent)
+    }
+
+    // How far the creature can still go on the grid, which is half of what remains
+    // while it crawls.
+    pub fn reach(&self) -> usize {
+        match self.prone {
+            true => self.remaining() / 2,
+            false => self.remaining(),
+        }
+    }
+
+    pub fn dash(&mut self) {
+        self.dashes += 1;
+    }
+
+    // Spends `cost` worth of movement, as priced by the grid, or nothing at all when
+    // not enough is left.
+    pub fn spend(&mut self, cost: usize) -> Result<(), GridError> {
+        let cost = match self.prone {
+            true => cost * 2,
+            false => cost,
+        };
+
+        self.charge(cost)
+    }
+
+    pub fn stand_up(&mut self) -> Result<(), GridError> {
+        if self.prone {
+            self.charge(self.effective_speed() / 2)?;
+            self.prone = false;
+        }
+
+        Ok(())
+    }
+
+    // Everything spent and dashed is per turn; speed and modifiers carry over.
+    pub fn start_turn(&mut self) {
+        self.spent = 0;
+        self.dashes = 0;
+    }
+
+    // `path_2d` from `start` with what is left of the budget.
+    pub fn range_2d(&self, grid: &Grid, start: &Location) -> Result<HashSet<Location>, GridError> {
+        path_2d(grid, start, self.reach())
+    }
+
+    fn charge(&mut self, cost: usize) -> Result<(), GridError> {
+        if cost > self.remaining() {
+            return Err(GridError::OutOfMovement(cost, self.remaining()));
+        }
+
+        self.spent += cost;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(MovementBudget::new(6), 6)]
+    #[case(MovementBudget { dashes: 1, ..MovementBudget::new(6) }, 12)]
+    #[case(MovementBudget { halved: true, ..MovementBudget::new(6) }, 3)]
+    #[case(MovementBudget { halved: true, dashes: 1, ..MovementBudget::new(6) }, 6)]
+    #[case(MovementBudget { spent: 4, ..MovementBudget::new(6) }, 2)]
+    #[case(MovementBudget { prone: true, ..MovementBudget::new(6) }, 3)]
+    fn reach(#[case] budget: MovementBudget, #[case] expected: usize) {
+        assert_eq!(budget.reach(), expected);
+    }
+
+    #[test]
+    fn spending_movement() {
+        let mut budget = MovementBudget::new(6);
+
+        budget.spend(4).unwrap();
+        assert_eq!(budget.spend(3), Err(GridError::OutOfMovement(3, 2)));
+        assert_eq!(budget.remaining(), 2);
+
+        budget.dash();
+        budget.spend(3).unwrap();
+        assert_eq!(budget.remaining(), 5);
+
+        budget.start_turn();
+        assert_eq!(budget.remaining(), 6);
+    }
+
+    #[test]
+    fn standing_up_costs_half_speed() {
+        let mut budget = MovementBudget {
+            prone: true,
+            ..MovementBudget::new(6)
+        };
+
+        budget.spend(1).unwrap();
+        assert_eq!(budget.spent, 2);
+
+        budget.stand_up().unwrap();
+        assert_eq!((budget.prone, budget.remaining()), (false, 1));
+
+        budget.stand_up().unwrap();
+        assert_eq!(budget.remaining(), 1);
+
+        let mut budget = MovementBudget {
+            prone: true,
+            spent: 4,
+            ..MovementBudget::new(6)
+        };
+        assert_eq!(budget.stand_up(), Err(GridError::OutOfMovement(3, 2)));
+        assert!(budget.prone);
+    }
+
+    #[test]
+    fn remaining_range_follows_the_budget() {
+        let grid = Grid::new(8, 1, 1);
+        let start = Location::new(0, 0, 0);
+        let mut budget = MovementBudget::new(6);
+
+        budget.spend(2).unwrap();
+        assert_eq!(
+            budget.range_2d(&grid, &start).unwrap(),
+            path_2d(&grid, &start, 4).unwrap()
+        );
+
+        budget.dash();
+        assert_eq!(budget.range_2d(&grid, &start).unwrap().len(), 7);
+    }
+}
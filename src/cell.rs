@@ -1,19 +1,58 @@
 use legion::Entity;
 
-use crate::prelude::{Direction, Location};
+use crate::prelude::{Direction, GridError, Location, Terrain};
 use std::collections::HashMap;
 
-#[derive(Clone)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Boundry {
     Full,
     Half,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ConnectorKind {
+    Stairs,
+    Ramp,
+}
+
+// Stairs or a ramp leading to the neighbor on the layer above or below, and what
+// walking along it costs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Connector {
+    pub kind: ConnectorKind,
+    pub cost: usize,
+}
+
+impl Connector {
+    pub fn stairs(cost: usize) -> Self {
+        Connector {
+            kind: ConnectorKind::Stairs,
+            cost,
+        }
+    }
+
+    pub fn ramp(cost: usize) -> Self {
+        Connector {
+            kind: ConnectorKind::Ramp,
+            cost,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "crate::serialization::CellRecord"))]
 pub struct Cell {
     pub loc: Location,
     pub boundries: Option<HashMap<Direction, Boundry>>,
-    pub is_difficult_terrain: bool,
+    pub connectors: Option<HashMap<Direction, Connector>>,
+    pub terrain: Terrain,
     pub is_obstructed: bool,
+    pub weight: Option<f64>,
+    pub edge_weights: Option<HashMap<Direction, f64>>,
     pub entities: Vec<legion::Entity>,
 }
 
@@ -22,41 +61,40 @@ impl Cell {
         Cell {
             loc,
             boundries: None,
-            is_difficult_terrain: false,
+            connectors: None,
+            terrain: Terrain::Floor,
             is_obstructed: false,
+            weight: None,
+            edge_weights: None,
             entities: vec![],
         }
     }
 
-    pub fn is_passable_to_neighbor(&self, new_loc: &Location) -> bool {
-        match &self.boundries {
-            Some(boundries) => {
-                let dir = self.loc.get_dir_from_loc(new_loc);
-
-                match boundries.get(&dir) {
-                    Some(boundry) => match *boundry {
-                        Boundry::Full => false,
-                        Boundry::Half => true,
-                    },
-                    None => true,
-                }
-            }
-            None => true,
-        }
+    pub fn is_passable_to_neighbor(&self, new_loc: &Location) -> Result<bool, GridError> {
+        Ok(!matches!(
+            self.get_boundry_to_neighbor(new_loc)?,
+            Some(Boundry::Full)
+        ))
     }
 
-    // TODO ERror handling for Full Wall
-    pub fn cost_to_neighbor(&self, new_loc: &Location) -> usize {
-        match &self.boundries {
-            Some(boundries) => {
-                let dir = self.loc.get_dir_from_loc(new_loc);
+    pub fn get_boundry_to_neighbor(
+        &self,
+        new_loc: &Location,
+    ) -> Result<Option<Boundry>, GridError> {
+        let dir = self.loc.get_dir_from_loc(new_loc)?;
 
-                match boundries.get(&dir) {
-                    Some(Boundry::Half) => 2,
-                    _ => 1,
-                }
-            }
-            None => 1,
+        Ok(self
+            .boundries
+            .as_ref()
+            .and_then(|boundries| boundries.get(&dir))
+            .copied())
+    }
+
+    pub fn cost_to_neighbor(&self, new_loc: &Location) -> Result<usize, GridError> {
+        match self.get_boundry_to_neighbor(new_loc)? {
+            Some(Boundry::Full) => Err(GridError::BlockedByWall(self.loc, *new_loc)),
+            Some(Boundry::Half) => Ok(2),
+            None => Ok(1),
         }
     }
 
@@ -69,6 +107,77 @@ impl Cell {
         self.boundries.as_mut().unwrap().insert(dir, boundry);
     }
 
+    pub fn remove_boundry(&mut self, dir: &Direction) {
+        if let Some(boundries) = self.boundries.as_mut() {
+            boundries.remove(dir);
+
+            if boundries.is_empty() {
+                self.boundries = None;
+            }
+        }
+    }
+
+    pub fn get_connector_to_neighbor(
+        &self,
+        new_loc: &Location,
+    ) -> Result<Option<Connector>, GridError> {
+        let dir = self.loc.get_dir_from_loc(new_loc)?;
+
+        Ok(self
+            .connectors
+            .as_ref()
+            .and_then(|connectors| connectors.get(&dir))
+            .copied())
+    }
+
+    pub fn add_connector(&mut self, dir: Direction, connector: Connector) -> Result<(), GridError> {
+        if !dir.is_vertical() {
+            return Err(GridError::NotVertical(self.loc, dir));
+        }
+
+        self.connectors
+            .get_or_insert_with(HashMap::new)
+            .insert(dir, connector);
+
+        Ok(())
+    }
+
+    pub fn remove_connector(&mut self, dir: &Direction) {
+        if let Some(connectors) = self.connectors.as_mut() {
+            connectors.remove(dir);
+
+            if connectors.is_empty() {
+                self.connectors = None;
+            }
+        }
+    }
+
+    pub fn get_edge_weight_to_neighbor(
+        &self,
+        new_loc: &Location,
+    ) -> Result<Option<f64>, GridError> {
+        let dir = self.loc.get_dir_from_loc(new_loc)?;
+
+        Ok(self
+            .edge_weights
+            .as_ref()
+            .and_then(|weights| weights.get(&dir))
+            .copied())
+    }
+
+    pub fn set_edge_weight(&mut self, dir: Direction, weight: Option<f64>) {
+        let weights = self.edge_weights.get_or_insert_with(HashMap::new);
+
+        match weight {
+            Some(weight) => weights.insert(dir, weight),
+            None => weights.remove(&dir),
+        };
+
+        if weights.is_empty() {
+            self.edge_weights = None;
+        }
+    }
+
     pub fn add_entity(&mut self, entity: Entity) {
         self.entities.push(entity);
     }
@@ -0,0 +1,228 @@
+use crate::prelude::{Grid, GridError, Location, MovementMode};
+use std::cmp::Ordering;
+use std::ops::Add;
+
+// What the weighted searches add up. Weights are stored on the grid as `f64` and
+// converted into the search's cost type, so integer costs round fractional
+// weights up.
+pub trait Cost: Copy + Ord + Add<Output = Self> {
+    fn zero() -> Self;
+    fn from_weight(weight: f64) -> Self;
+}
+
+macro_rules! integer_cost {
+    ($($int:ty),*) => {
+        $(
+            impl Cost for $int {
+                fn zero() -> Self {
+                    0
+                }
+
+                fn from_weight(weight: f64) -> Self {
+                    weight.max(0.0).ceil() as $int
+                }
+            }
+        )*
+    };
+}
+
+integer_cost!(u8, u16, u32, u64, usize);
+
+// Weights must be finite and not negative, or searches could loop forever around
+// a cycle that keeps getting cheaper.
+pub(crate) fn is_valid_weight(weight: f64) -> bool {
+    weight.is_finite() && weight >= 0.0
+}
+
+// An `f64` cost, ordered with `f64::total_cmp` so it can be used as a search key.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FloatCost(pub f64);
+
+impl PartialEq for FloatCost {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for FloatCost {}
+
+impl PartialOrd for FloatCost {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for FloatCost {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl Add for FloatCost {
+    type Output = FloatCost;
+
+    fn add(self, other: FloatCost) -> FloatCost {
+        FloatCost(self.0 + other.0)
+    }
+}
+
+impl Cost for FloatCost {
+    fn zero() -> Self {
+        FloatCost(0.0)
+    }
+
+    fn from_weight(weight: f64) -> Self {
+        FloatCost(weight)
+    }
+}
+
+impl Grid {
+    // `cost_for_mode` with the cell's weight in place of its terrain cost and the
+    // edge's weight in place of its wall cost. Without an edge weight, a step that
+    // crosses no walls costs just what entering the cell does, so cell weights
+    // below 1 still count. Steps that are not allowed stay errors whatever their
+    // weights.
+    pub fn weighted_cost<C: Cost>(
+        &self,
+        current_loc: &Location,
+        new_loc: &Location,
+        mode: MovementMode,
+    ) -> Result<C, GridError> {
+        let (boundry_cost, terrain_cost) = self.mode_costs(current_loc, new_loc, mode)?;
+
+        let boundry_weight = match self.edge_weight_between(current_loc, new_loc)? {
+            Some(weight) => weight,
+            None if boundry_cost > 1 => boundry_cost as f64,
+            None => 0.0,
+        };
+        let terrain_weight = self
+            .get_ref_cell(new_loc)?
+            .weight
+            .unwrap_or(terrain_cost as f64);
+
+        Ok(C::from_weight(boundry_weight.max(terrain_weight)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::{weighted_path_2d, Boundry, Cell, Direction};
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(1.0, 1)]
+    #[case(1.5, 2)]
+    #[case(-3.0, 0)]
+    fn integer_costs_round_up(#[case] weight: f64, #[case] expected: u32) {
+        assert_eq!(u32::from_weight(weight), expected);
+    }
+
+    #[test]
+    fn weights_replace_terrain_and_wall_costs() {
+        let mut grid = Grid::from_ascii("+ + + +\n . ~ .\n+ + + +").unwrap();
+        let (first, second, third) = (
+            Location::new(0, 0, 0),
+            Location::new(1, 0, 0),
+            Location::new(2, 0, 0),
+        );
+
+        assert_eq!(
+            grid.weighted_cost(&first, &second, MovementMode::Walk),
+            Ok(2u32)
+        );
+
+        grid.set_cell_weight(&second, Some(1.5)).unwrap();
+        assert_eq!(
+            grid.weighted_cost(&first, &second, MovementMode::Walk),
+            Ok(FloatCost(1.5))
+        );
+        assert_eq!(
+            grid.weighted_cost(&first, &second, MovementMode::Walk),
+            Ok(2u32)
+        );
+
+        grid.set_edge_weight(&third, Direction::W, Some(3.0))
+            .unwrap();
+        assert_eq!(
+            grid.weighted_cost(&second, &third, MovementMode::Walk),
+            Ok(FloatCost(3.0))
+        );
+        assert_eq!(
+            grid.weighted_cost(&third, &second, MovementMode::Walk),
+            Ok(FloatCost(3.0))
+        );
+    }
+
+    #[test]
+    fn roads_cost_less_than_floor() {
+        let mut grid = Grid::new(3, 1, 1);
+        let (first, second, third) = (
+            Location::new(0, 0, 0),
+            Location::new(1, 0, 0),
+            Location::new(2, 0, 0),
+        );
+
+        for loc in [second, third].iter() {
+            grid.set_cell_weight(loc, Some(0.5)).unwrap();
+        }
+
+        assert_eq!(
+            grid.weighted_cost(&first, &second, MovementMode::Walk),
+            Ok(FloatCost(0.5))
+        );
+        assert_eq!(
+            weighted_path_2d(&grid, &first, &third),
+            Ok(Some((vec![second, third], FloatCost(1.0))))
+        );
+
+        grid.add_cell_boundry_and_adjacent(&second, Direction::E, Boundry::Half)
+            .unwrap();
+        assert_eq!(
+            grid.weighted_cost(&second, &third, MovementMode::Walk),
+            Ok(FloatCost(2.0))
+        );
+
+        grid.set_edge_weight(&second, Direction::E, Some(0.25))
+            .unwrap();
+        assert_eq!(
+            grid.weighted_cost(&second, &third, MovementMode::Walk),
+            Ok(FloatCost(0.5))
+        );
+    }
+
+    #[rstest]
+    #[case(-0.5)]
+    #[case(f64::NAN)]
+    #[case(f64::INFINITY)]
+    fn invalid_weights_are_rejected(#[case] weight: f64) {
+        let mut grid = Grid::new(2, 1, 1);
+        let loc = Location::new(0, 0, 0);
+
+        assert_eq!(
+            grid.set_cell_weight(&loc, Some(weight)),
+            Err(GridError::InvalidWeight(loc))
+        );
+        assert_eq!(
+            grid.set_edge_weight(&loc, Direction::E, Some(weight)),
+            Err(GridError::InvalidWeight(loc))
+        );
+        assert_eq!(grid, Grid::new(2, 1, 1));
+
+        let mut cell = Cell::new_empty(loc);
+        cell.weight = Some(weight);
+        assert_eq!(
+            Grid::from_cells(2, 1, 1, vec![cell]),
+            Err(GridError::InvalidWeight(loc))
+        );
+    }
+
+    #[test]
+    fn float_costs_are_ordered() {
+        let mut costs = vec![FloatCost(2.5), FloatCost(0.5), FloatCost(1.5)];
+        costs.sort();
+
+        assert_eq!(costs, vec![FloatCost(0.5), FloatCost(1.5), FloatCost(2.5)]);
+        assert_eq!(FloatCost(0.5) + FloatCost(1.0), FloatCost(1.5));
+    }
+}
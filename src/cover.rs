@@ -0,0 +1,357 @@
+use crate::prelude::{Boundry, Direction, Grid, GridError, Location};
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Cover {
+    None,
+    Half,
+    ThreeQuarters,
+    Full,
+}
+
+type Point = (i32, i32);
+
+#[derive(Clone, Copy)]
+enum Shape {
+    Square(Point),
+    Wall(Point, Point),
+    Seam(Point, Point),
+    Post(Point),
+}
+
+struct Obstacle {
+    shape: Shape,
+    is_low: bool,
+}
+
+impl Grid {
+    // Traces lines from every corner of the attacker's square to the corners of the
+    // target's square on the attacker's layer, taking the corner that grants the
+    // least cover. Lines blocked only by half walls or creatures give half cover, and
+    // only a target that no line reaches has full cover.
+    pub fn cover_between(
+        &self,
+        attacker: &Location,
+        target: &Location,
+        count_creatures: bool,
+    ) -> Result<Cover, GridError> {
+        self.get_ref_cell(attacker)?;
+        self.get_ref_cell(target)?;
+
+        let obstacles = self.cover_obstacles(attacker, target, count_creatures);
+
+        let lines = corners(attacker)
+            .iter()
+            .map(|from| {
+                let mut blocked = 0;
+                let mut low_blocked = 0;
+
+                for to in corners(target).iter() {
+                    let hits = obstacles
+                        .iter()
+                        .filter(|obstacle| obstacle.shape.blocks_line(*from, *to));
+
+                    match hits.map(|obstacle| obstacle.is_low).min() {
+                        Some(false) => blocked += 1,
+                        Some(true) => low_blocked += 1,
+                        None => {}
+                    }
+                }
+
+                (blocked, low_blocked)
+            })
+            .collect::<Vec<(usize, usize)>>();
+
+        if lines.iter().all(|(blocked, _)| *blocked == 4) {
+            return Ok(Cover::Full);
+        }
+
+        Ok(lines
+            .into_iter()
+            .map(|(blocked, low_blocked)| lines_cover(blocked, low_blocked))
+            .min()
+            .unwrap())
+    }
+
+    fn cover_obstacles(
+        &self,
+        attacker: &Location,
+        target: &Location,
+        count_creatures: bool,
+    ) -> Vec<Obstacle> {
+        let mut obstacles = vec![];
+        let mut walls = BTreeSet::new();
+        let mut seams = BTreeSet::new();
+
+        for x in attacker.x.min(target.x) - 1..=attacker.x.max(target.x) + 1 {
+            for z in attacker.z.min(target.z) - 1..=attacker.z.max(target.z) + 1 {
+                let loc = Location::new(x, attacker.y, z);
+                let cell = match self.get_ref_cell(&loc) {
+                    Ok(cell) => cell,
+                    Err(_) => continue,
+                };
+                let (x, z) = (x as i32, z as i32);
+                let is_occupant = (loc.x, loc.z) == (attacker.x, attacker.z)
+                    || (loc.x, loc.z) == (target.x, target.z);
+
+                if cell.is_obstructed && !is_occupant {
+                    obstacles.push(Obstacle {
+                        shape: Shape::Square((x, z)),
+                        is_low: false,
+                    });
+
+                    let neighbors = [
+                        (Direction::E, ((x + 1, z), (x + 1, z + 1))),
+                        (Direction::S, ((x, z + 1), (x + 1, z + 1))),
+                    ];
+
+                    for (dir, seam) in neighbors.iter() {
+                        let is_neighbor_obstructed = self
+                            .get_ref_cell(&loc.get_loc_from_dir(dir))
+                            .is_ok_and(|cell| cell.is_obstructed);
+
+                        if is_neighbor_obstructed {
+                            seams.insert(*seam);
+                        }
+                    }
+                } else if count_creatures && !is_occupant && !cell.entities.is_empty() {
+                    obstacles.push(Obstacle {
+                        shape: Shape::Square((x, z)),
+                        is_low: true,
+                    });
+                }
+
+                for (dir, boundry) in cell.boundries.iter().flatten() {
+                    let is_low = *boundry == Boundry::Half;
+
+                    match edge_of((x, z), dir) {
+                        Some(edge) => {
+                            walls.insert((is_low, edge));
+                        }
+                        None => {
+                            if let Some(point) = corner_of((x, z), dir) {
+                                obstacles.push(Obstacle {
+                                    shape: Shape::Post(point),
+                                    is_low,
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // Neighbouring wall edges are merged, otherwise a line through the joint
+        // between two of them would slip through the wall.
+        let mut runs: BTreeMap<(bool, bool, i32), Vec<(i32, i32)>> = BTreeMap::new();
+
+        for (is_low, (from, to)) in walls.into_iter() {
+            let is_vertical = from.0 == to.0;
+            let (fixed, start) = if is_vertical { from } else { (from.1, from.0) };
+            let key = (is_low, is_vertical, fixed);
+            let run = runs.entry(key).or_default();
+
+            match run.last_mut() {
+                Some(last) if last.1 == start => last.1 = start + 1,
+                _ => run.push((start, start + 1)),
+            }
+        }
+
+        for ((is_low, is_vertical, fixed), run) in runs.into_iter() {
+            for (start, end) in run.into_iter() {
+                let (from, to) = if is_vertical {
+                    ((fixed, start), (fixed, end))
+                } else {
+                    ((start, fixed), (end, fixed))
+                };
+
+                obstacles.push(Obstacle {
+                    shape: Shape::Wall(from, to),
+                    is_low,
+                });
+            }
+        }
+
+        for (from, to) in seams.into_iter() {
+            obstacles.push(Obstacle {
+                shape: Shape::Seam(from, to),
+                is_low: false,
+            });
+        }
+
+        obstacles
+    }
+}
+
+impl Shape {
+    fn blocks_line(&self, from: Point, to: Point) -> bool {
+        match *self {
+            // Line endpoints are always square corners, so a line reaches inside
+            // a square exactly when it properly crosses one of its diagonals.
+            Shape::Square((x, z)) => {
+                crosses(from, to, (x, z), (x + 1, z + 1))
+                    || crosses(from, to, (x + 1, z), (x, z + 1))
+            }
+            Shape::Wall(start, end) => crosses(from, to, start, end),
+            Shape::Seam(start, end) => overlaps(from, to, start, end),
+            Shape::Post(point) => {
+                orient(from, to, point) == 0
+                    && point != from
+                    && point != to
+                    && point.0 >= from.0.min(to.0)
+                    && point.0 <= from.0.max(to.0)
+                    && point.1 >= from.1.min(to.1)
+                    && point.1 <= from.1.max(to.1)
+            }
+        }
+    }
+}
+
+fn corners(loc: &Location) -> [Point; 4] {
+    let (x, z) = (loc.x as i32, loc.z as i32);
+    [(x, z), (x + 1, z), (x, z + 1), (x + 1, z + 1)]
+}
+
+fn edge_of((x, z): Point, dir: &Direction) -> Option<(Point, Point)> {
+    match *dir {
+        Direction::N => Some(((x, z), (x + 1, z))),
+        Direction::S => Some(((x, z + 1), (x + 1, z + 1))),
+        Direction::W => Some(((x, z), (x, z + 1))),
+        Direction::E => Some(((x + 1, z), (x + 1, z + 1))),
+        _ => None,
+    }
+}
+
+fn corner_of((x, z): Point, dir: &Direction) -> Option<Point> {
+    match *dir {
+        Direction::NW => Some((x, z)),
+        Direction::NE => Some((x + 1, z)),
+        Direction::SW => Some((x, z + 1)),
+        Direction::SE => Some((x + 1, z + 1)),
+        _ => None,
+    }
+}
+
+// Cover from the lines traced from one corner of the attacker's square.
+fn lines_cover(blocked: usize, low_blocked: usize) -> Cover {
+    match (blocked, low_blocked) {
+        (3..=4, _) => Cover::ThreeQuarters,
+        (0, 0) => Cover::None,
+        _ => Cover::Half,
+    }
+}
+
+fn orient(a: Point, b: Point, c: Point) -> i32 {
+    (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)
+}
+
+fn crosses(a: Point, b: Point, c: Point, d: Point) -> bool {
+    orient(a, b, c).signum() * orient(a, b, d).signum() < 0
+        && orient(c, d, a).signum() * orient(c, d, b).signum() < 0
+}
+
+fn overlaps(a: Point, b: Point, c: Point, d: Point) -> bool {
+    if orient(a, b, c) != 0 || orient(a, b, d) != 0 {
+        return false;
+    }
+
+    let project = |p: Point| if a.0 != b.0 { p.0 } else { p.1 };
+    let (line_min, line_max) = (project(a).min(project(b)), project(a).max(project(b)));
+    let (edge_min, edge_max) = (project(c).min(project(d)), project(c).max(project(d)));
+
+    line_min.max(edge_min) < line_max.min(edge_max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use legion::World;
+    use rstest::{fixture, rstest};
+
+    #[fixture]
+    fn grid() -> Grid {
+        Grid::new(6, 5, 1)
+    }
+
+    fn wall_column(grid: &mut Grid, x: i8, boundry: Boundry) {
+        for z in 0..grid.width {
+            grid.add_cell_boundry_and_adjacent(&Location::new(x, 0, z), Direction::E, boundry)
+                .unwrap();
+        }
+    }
+
+    #[rstest]
+    fn no_cover_in_the_open(grid: Grid) {
+        let result = grid
+            .cover_between(&Location::new(0, 0, 2), &Location::new(4, 0, 2), true)
+            .unwrap();
+        assert_eq!(result, Cover::None);
+    }
+
+    #[rstest]
+    fn obstruction_in_between_gives_half_cover(mut grid: Grid) {
+        grid.toggle_cell_obstruction(&Location::new(2, 0, 2))
+            .unwrap();
+
+        let result = grid
+            .cover_between(&Location::new(0, 0, 2), &Location::new(4, 0, 2), true)
+            .unwrap();
+        assert_eq!(result, Cover::Half);
+    }
+
+    #[rstest]
+    fn staggered_obstructions_give_three_quarters_cover(mut grid: Grid) {
+        grid.toggle_cell_obstruction(&Location::new(1, 0, 1))
+            .unwrap();
+        grid.toggle_cell_obstruction(&Location::new(3, 0, 1))
+            .unwrap();
+
+        let result = grid
+            .cover_between(&Location::new(0, 0, 0), &Location::new(4, 0, 2), true)
+            .unwrap();
+        assert_eq!(result, Cover::ThreeQuarters);
+    }
+
+    #[rstest]
+    #[case(0, 0, Cover::None)]
+    #[case(1, 0, Cover::Half)]
+    #[case(0, 4, Cover::Half)]
+    #[case(2, 2, Cover::Half)]
+    #[case(3, 1, Cover::ThreeQuarters)]
+    #[case(4, 0, Cover::ThreeQuarters)]
+    fn blocked_lines(#[case] blocked: usize, #[case] low_blocked: usize, #[case] expected: Cover) {
+        assert_eq!(lines_cover(blocked, low_blocked), expected);
+    }
+
+    #[rstest]
+    #[case(Boundry::Full, Cover::Full)]
+    #[case(Boundry::Half, Cover::Half)]
+    fn wall_between(mut grid: Grid, #[case] boundry: Boundry, #[case] expected: Cover) {
+        wall_column(&mut grid, 2, boundry);
+
+        let result = grid
+            .cover_between(&Location::new(0, 0, 2), &Location::new(4, 0, 2), true)
+            .unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[rstest]
+    #[case(true, Cover::Half)]
+    #[case(false, Cover::None)]
+    fn creatures_between(mut grid: Grid, #[case] count_creatures: bool, #[case] expected: Cover) {
+        let mut world = World::default();
+        let entity = world.push(());
+        grid.get_mut_cell(&Location::new(2, 0, 2))
+            .unwrap()
+            .add_entity(entity);
+
+        let result = grid
+            .cover_between(
+                &Location::new(0, 0, 2),
+                &Location::new(4, 0, 2),
+                count_creatures,
+            )
+            .unwrap();
+        assert_eq!(result, expected);
+    }
+}
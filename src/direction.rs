@@ -1,4 +1,7 @@
-#[derive(Clone, PartialEq, Eq, Hash)]
+use crate::offset::Offset;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Direction {
     NW,
     N,
@@ -28,7 +31,91 @@ pub enum Direction {
     DSE,
 }
 
+static DIRECTIONS: [Direction; 26] = [
+    Direction::NW,
+    Direction::N,
+    Direction::NE,
+    Direction::W,
+    Direction::E,
+    Direction::SW,
+    Direction::S,
+    Direction::SE,
+    Direction::U,
+    Direction::D,
+    Direction::UNW,
+    Direction::UN,
+    Direction::UNE,
+    Direction::UW,
+    Direction::UE,
+    Direction::USW,
+    Direction::US,
+    Direction::USE,
+    Direction::DNW,
+    Direction::DN,
+    Direction::DNE,
+    Direction::DW,
+    Direction::DE,
+    Direction::DSW,
+    Direction::DS,
+    Direction::DSE,
+];
+
+// Horizontal directions in clockwise order, starting from north.
+static COMPASS: [Direction; 8] = [
+    Direction::N,
+    Direction::NE,
+    Direction::E,
+    Direction::SE,
+    Direction::S,
+    Direction::SW,
+    Direction::W,
+    Direction::NW,
+];
+
 impl Direction {
+    pub fn all_2d() -> impl Iterator<Item = Direction> {
+        DIRECTIONS[..8].iter().copied()
+    }
+
+    pub fn all_3d() -> impl Iterator<Item = Direction> {
+        DIRECTIONS.iter().copied()
+    }
+
+    pub fn offset(&self) -> Offset {
+        match *self {
+            Direction::NW => Offset::new(-1, 0, -1),
+            Direction::N => Offset::new(0, 0, -1),
+            Direction::NE => Offset::new(1, 0, -1),
+            Direction::W => Offset::new(-1, 0, 0),
+            Direction::E => Offset::new(1, 0, 0),
+            Direction::SW => Offset::new(-1, 0, 1),
+            Direction::S => Offset::new(0, 0, 1),
+            Direction::SE => Offset::new(1, 0, 1),
+            Direction::U => Offset::new(0, 1, 0),
+            Direction::D => Offset::new(0, -1, 0),
+            Direction::UNW => Offset::new(-1, 1, -1),
+            Direction::UN => Offset::new(0, 1, -1),
+            Direction::UNE => Offset::new(1, 1, -1),
+            Direction::UW => Offset::new(-1, 1, 0),
+            Direction::UE => Offset::new(1, 1, 0),
+            Direction::USW => Offset::new(-1, 1, 1),
+            Direction::US => Offset::new(0, 1, 1),
+            Direction::USE => Offset::new(1, 1, 1),
+            Direction::DNW => Offset::new(-1, -1, -1),
+            Direction::DN => Offset::new(0, -1, -1),
+            Direction::DNE => Offset::new(1, -1, -1),
+            Direction::DW => Offset::new(-1, -1, 0),
+            Direction::DE => Offset::new(1, -1, 0),
+            Direction::DSW => Offset::new(-1, -1, 1),
+            Direction::DS => Offset::new(0, -1, 1),
+            Direction::DSE => Offset::new(1, -1, 1),
+        }
+    }
+
+    pub fn from_offset(offset: &Offset) -> Option<Direction> {
+        Direction::all_3d().find(|dir| dir.offset() == *offset)
+    }
+
     pub fn get_opposite(&self) -> Direction {
         match *self {
             Direction::NW => Direction::SE,
@@ -59,4 +146,124 @@ impl Direction {
             Direction::DSE => Direction::UNW,
         }
     }
+
+    pub fn is_diagonal(&self) -> bool {
+        let offset = self.offset();
+        (offset.x != 0) as u8 + (offset.y != 0) as u8 + (offset.z != 0) as u8 > 1
+    }
+
+    pub fn is_vertical(&self) -> bool {
+        self.offset().y != 0
+    }
+
+    pub fn rotate_cw_45(&self) -> Direction {
+        self.rotate_horizontal(1)
+    }
+
+    pub fn rotate_ccw_45(&self) -> Direction {
+        self.rotate_horizontal(7)
+    }
+
+    pub fn rotate_cw_90(&self) -> Direction {
+        self.rotate_horizontal(2)
+    }
+
+    pub fn rotate_ccw_90(&self) -> Direction {
+        self.rotate_horizontal(6)
+    }
+
+    pub fn mirror_x(&self) -> Direction {
+        let offset = self.offset();
+        self.with_offset(Offset::new(-offset.x, offset.y, offset.z))
+    }
+
+    pub fn mirror_y(&self) -> Direction {
+        let offset = self.offset();
+        self.with_offset(Offset::new(offset.x, -offset.y, offset.z))
+    }
+
+    pub fn mirror_z(&self) -> Direction {
+        let offset = self.offset();
+        self.with_offset(Offset::new(offset.x, offset.y, -offset.z))
+    }
+
+    // Rotates the horizontal part of the direction by 45° steps, keeping its
+    // vertical part, so UN turns into UNE and U stays U.
+    fn rotate_horizontal(&self, steps: usize) -> Direction {
+        let offset = self.offset();
+        let horizontal = Offset::new(offset.x, 0, offset.z);
+
+        match COMPASS.iter().position(|dir| dir.offset() == horizontal) {
+            Some(idx) => {
+                let rotated = COMPASS[(idx + steps) % COMPASS.len()].offset();
+                self.with_offset(Offset::new(rotated.x, offset.y, rotated.z))
+            }
+            None => *self,
+        }
+    }
+
+    fn with_offset(&self, offset: Offset) -> Direction {
+        Direction::from_offset(&offset).unwrap_or(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[test]
+    fn offsets_round_trip() {
+        for dir in Direction::all_3d() {
+            assert_eq!(Direction::from_offset(&dir.offset()), Some(dir));
+            assert_eq!(dir.get_opposite().offset(), -dir.offset());
+        }
+
+        assert_eq!(Direction::from_offset(&Offset::new(2, 0, 0)), None);
+        assert_eq!(Direction::from_offset(&Offset::default()), None);
+    }
+
+    #[test]
+    fn all_directions() {
+        assert_eq!(Direction::all_2d().count(), 8);
+        assert!(Direction::all_2d().all(|dir| !dir.is_vertical()));
+        assert_eq!(Direction::all_3d().count(), 26);
+    }
+
+    #[rstest]
+    #[case(Direction::N, Direction::NE, Direction::E)]
+    #[case(Direction::NW, Direction::N, Direction::NE)]
+    #[case(Direction::W, Direction::NW, Direction::N)]
+    #[case(Direction::DS, Direction::DSW, Direction::DW)]
+    #[case(Direction::U, Direction::U, Direction::U)]
+    fn rotations(#[case] dir: Direction, #[case] cw_45: Direction, #[case] cw_90: Direction) {
+        assert_eq!(dir.rotate_cw_45(), cw_45);
+        assert_eq!(dir.rotate_cw_90(), cw_90);
+        assert_eq!(cw_45.rotate_ccw_45(), dir);
+        assert_eq!(cw_90.rotate_ccw_90(), dir);
+    }
+
+    #[rstest]
+    #[case(Direction::NE, Direction::NW, Direction::NE, Direction::SE)]
+    #[case(Direction::UW, Direction::UE, Direction::DW, Direction::UW)]
+    fn mirrors(
+        #[case] dir: Direction,
+        #[case] x: Direction,
+        #[case] y: Direction,
+        #[case] z: Direction,
+    ) {
+        assert_eq!(dir.mirror_x(), x);
+        assert_eq!(dir.mirror_y(), y);
+        assert_eq!(dir.mirror_z(), z);
+    }
+
+    #[rstest]
+    #[case(Direction::N, false, false)]
+    #[case(Direction::SE, true, false)]
+    #[case(Direction::D, false, true)]
+    #[case(Direction::UN, true, true)]
+    fn classification(#[case] dir: Direction, #[case] diagonal: bool, #[case] vertical: bool) {
+        assert_eq!(dir.is_diagonal(), diagonal);
+        assert_eq!(dir.is_vertical(), vertical);
+    }
 }
@@ -0,0 +1,186 @@
+use crate::prelude::{path_2d, Grid, GridError, Location};
+use legion::{storage::IntoComponentSource, system, systems::CommandBuffer, Entity, World};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct GridPosition(pub Location);
+
+// Speed is the movement cost an entity may spend on a single move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Movement {
+    pub speed: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MoveIntent {
+    pub to: Location,
+}
+
+// Added in place of a `MoveIntent` the grid could not carry out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MoveRejected {
+    pub to: Location,
+}
+
+// Spawns an entity into a grid cell with a matching `GridPosition`, which is how
+// entities should join the grid so the two agree from the start.
+pub fn spawn_on_grid<T>(
+    world: &mut World,
+    grid: &mut Grid,
+    loc: &Location,
+    components: T,
+) -> Result<Entity, GridError>
+where
+    Option<T>: IntoComponentSource,
+{
+    grid.get_ref_cell(loc)?;
+
+    let entity = world.push(components);
+    world
+        .entry(entity)
+        .unwrap()
+        .add_component(GridPosition(*loc));
+    grid.add_entity(loc, entity)?;
+
+    Ok(entity)
+}
+
+// Carries out every `MoveIntent` the entity's speed can reach on the `Grid`
+// resource, moving it between cells and updating its `GridPosition` together.
+// Intents are consumed either way, and unreachable ones leave a `MoveRejected`.
+#[system(for_each)]
+pub fn grid_movement(
+    entity: &Entity,
+    position: &mut GridPosition,
+    movement: &Movement,
+    intent: &MoveIntent,
+    commands: &mut CommandBuffer,
+    #[resource] grid: &mut Grid,
+) {
+    commands.remove_component::<MoveIntent>(*entity);
+
+    if intent.to == position.0 {
+        return;
+    }
+
+    let is_reachable = path_2d(grid, &position.0, movement.speed)
+        .is_ok_and(|reachable| reachable.contains(&intent.to));
+
+    if is_reachable && grid.move_entity(entity, &intent.to).is_ok() {
+        position.0 = intent.to;
+    } else {
+        commands.add_component(*entity, MoveRejected { to: intent.to });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use legion::{IntoQuery, Resources, Schedule};
+    use rstest::{fixture, rstest};
+
+    #[fixture]
+    fn grid() -> Grid {
+        Grid::from_ascii(
+            "\
++ + + + +
+ . . # .
++ + + + +
+ . ~ # .
++ + + + +
+ . . . .
++ + + + +
+",
+        )
+        .unwrap()
+    }
+
+    fn run(world: &mut World, grid: Grid) -> Grid {
+        let mut resources = Resources::default();
+        resources.insert(grid);
+
+        let mut schedule = Schedule::builder()
+            .add_system(grid_movement_system())
+            .build();
+        schedule.execute(world, &mut resources);
+
+        resources.remove::<Grid>().unwrap()
+    }
+
+    fn spawn(
+        world: &mut World,
+        grid: &mut Grid,
+        loc: Location,
+        speed: usize,
+        to: Location,
+    ) -> Entity {
+        spawn_on_grid(world, grid, &loc, (Movement { speed }, MoveIntent { to })).unwrap()
+    }
+
+    #[rstest]
+    #[case(Location::new(1, 0, 2), 2, true)]
+    #[case(Location::new(3, 0, 0), 2, false)]
+    #[case(Location::new(3, 0, 0), 6, true)]
+    #[case(Location::new(2, 0, 0), 4, false)]
+    fn moves_reachable_intents(
+        mut grid: Grid,
+        #[case] to: Location,
+        #[case] speed: usize,
+        #[case] moved: bool,
+    ) {
+        let mut world = World::default();
+        let start = Location::new(0, 0, 0);
+        let entity = spawn(&mut world, &mut grid, start, speed, to);
+
+        let grid = run(&mut world, grid);
+
+        let end = if moved { to } else { start };
+        let entry = world.entry(entity).unwrap();
+
+        assert_eq!(
+            entry.get_component::<GridPosition>(),
+            Ok(&GridPosition(end))
+        );
+        assert!(entry.get_component::<MoveIntent>().is_err());
+        assert_eq!(entry.get_component::<MoveRejected>().is_ok(), !moved);
+        assert_eq!(grid.get_ref_cell(&end).unwrap().entities, vec![entity]);
+    }
+
+    #[rstest]
+    fn positions_match_cells(mut grid: Grid) {
+        let mut world = World::default();
+
+        spawn(
+            &mut world,
+            &mut grid,
+            Location::new(0, 0, 0),
+            3,
+            Location::new(1, 0, 2),
+        );
+        spawn(
+            &mut world,
+            &mut grid,
+            Location::new(3, 0, 2),
+            3,
+            Location::new(3, 0, 0),
+        );
+
+        let grid = run(&mut world, grid);
+
+        let mut query = <(Entity, &GridPosition)>::query();
+
+        for (entity, position) in query.iter(&world) {
+            assert!(grid
+                .get_ref_cell(&position.0)
+                .unwrap()
+                .entities
+                .contains(entity));
+        }
+
+        assert_eq!(
+            grid.cells_iter()
+                .map(|cell| cell.entities.len())
+                .sum::<usize>(),
+            2
+        );
+    }
+}
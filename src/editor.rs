@@ -0,0 +1,450 @@
+use crate::prelude::{Boundry, Cell, Direction, Grid, GridError, Location, Terrain};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GridCommand {
+    ToggleObstruction(Location),
+    ToggleDifficultTerrain(Location),
+    SetTerrain(Location, Terrain),
+    AddBoundry(Location, Direction, Boundry),
+    RemoveBoundry(Location, Direction),
+}
+
+// What a command changed. Boundry edits change both sides of an edge and remember
+// each side, so undoing restores walls that were only set on one side of it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Edit {
+    Obstruction(Location),
+    Terrain {
+        loc: Location,
+        before: Terrain,
+        after: Terrain,
+    },
+    Boundry {
+        loc: Location,
+        dir: Direction,
+        before: (Option<Boundry>, Option<Boundry>),
+        after: Option<Boundry>,
+    },
+}
+
+#[derive(Debug)]
+pub struct GridEditor {
+    grid: Grid,
+    undo_stack: Vec<Vec<Edit>>,
+    redo_stack: Vec<Vec<Edit>>,
+    transaction: Option<Vec<Edit>>,
+}
+
+impl GridEditor {
+    pub fn new(grid: Grid) -> Self {
+        GridEditor {
+            grid,
+            undo_stack: vec![],
+            redo_stack: vec![],
+            transaction: None,
+        }
+    }
+
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    pub fn into_grid(self) -> Grid {
+        self.grid
+    }
+
+    pub fn apply(&mut self, command: GridCommand) -> Result<(), GridError> {
+        let edits = self.edits_for(&command)?;
+
+        for edit in edits.iter() {
+            apply_edit(&mut self.grid, edit, true);
+        }
+
+        match self.transaction.as_mut() {
+            Some(transaction) => transaction.extend(edits),
+            None => {
+                self.undo_stack.push(edits);
+                self.redo_stack.clear();
+            }
+        }
+
+        Ok(())
+    }
+
+    // Runs `edit` as one undo step. If it fails, everything it applied is reverted.
+    // Nested transactions fold into the outermost one.
+    pub fn transaction<F>(&mut self, edit: F) -> Result<(), GridError>
+    where
+        F: FnOnce(&mut GridEditor) -> Result<(), GridError>,
+    {
+        let is_outermost = self.transaction.is_none();
+        let start = self.transaction.get_or_insert_with(Vec::new).len();
+
+        let result = edit(self);
+
+        if result.is_err() {
+            let transaction = self.transaction.as_mut().unwrap();
+
+            for edit in transaction.drain(start..).rev() {
+                apply_edit(&mut self.grid, &edit, false);
+            }
+        }
+
+        if is_outermost {
+            let edits = self.transaction.take().unwrap();
+
+            if !edits.is_empty() {
+                self.undo_stack.push(edits);
+                self.redo_stack.clear();
+            }
+        }
+
+        result
+    }
+
+    pub fn undo(&mut self) -> bool {
+        if self.transaction.is_some() {
+            return false;
+        }
+
+        match self.undo_stack.pop() {
+            Some(edits) => {
+                for edit in edits.iter().rev() {
+                    apply_edit(&mut self.grid, edit, false);
+                }
+
+                self.redo_stack.push(edits);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn redo(&mut self) -> bool {
+        if self.transaction.is_some() {
+            return false;
+        }
+
+        match self.redo_stack.pop() {
+            Some(edits) => {
+                for edit in edits.iter() {
+                    apply_edit(&mut self.grid, edit, true);
+                }
+
+                self.undo_stack.push(edits);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.transaction.is_none() && !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.transaction.is_none() && !self.redo_stack.is_empty()
+    }
+
+    fn edits_for(&self, command: &GridCommand) -> Result<Vec<Edit>, GridError> {
+        match *command {
+            GridCommand::ToggleObstruction(loc) => {
+                self.grid.get_ref_cell(&loc)?;
+                Ok(vec![Edit::Obstruction(loc)])
+            }
+            GridCommand::ToggleDifficultTerrain(loc) => {
+                let after = match self.grid.get_ref_cell(&loc)?.terrain {
+                    Terrain::Difficult => Terrain::Floor,
+                    _ => Terrain::Difficult,
+                };
+
+                self.terrain_edits(loc, after)
+            }
+            GridCommand::SetTerrain(loc, terrain) => self.terrain_edits(loc, terrain),
+            GridCommand::AddBoundry(loc, dir, boundry) => {
+                self.boundry_edits(loc, dir, Some(boundry))
+            }
+            GridCommand::RemoveBoundry(loc, dir) => self.boundry_edits(loc, dir, None),
+        }
+    }
+
+    fn terrain_edits(&self, loc: Location, after: Terrain) -> Result<Vec<Edit>, GridError> {
+        Ok(vec![Edit::Terrain {
+            loc,
+            before: self.grid.get_ref_cell(&loc)?.terrain,
+            after,
+        }])
+    }
+
+    fn boundry_edits(
+        &self,
+        loc: Location,
+        dir: Direction,
+        after: Option<Boundry>,
+    ) -> Result<Vec<Edit>, GridError> {
+        let here = boundry_at(self.grid.get_ref_cell(&loc)?, &dir);
+        let there = self
+            .grid
+            .get_ref_cell(&loc.get_loc_from_dir(&dir))
+            .ok()
+            .and_then(|cell| boundry_at(cell, &dir.get_opposite()));
+
+        Ok(vec![Edit::Boundry {
+            loc,
+            dir,
+            before: (here, there),
+            after,
+        }])
+    }
+}
+
+fn boundry_at(cell: &Cell, dir: &Direction) -> Option<Boundry> {
+    cell.boundries
+        .as_ref()
+        .and_then(|boundries| boundries.get(dir))
+        .copied()
+}
+
+// Edits are only recorded for cells that exist, so applying them cannot fail.
+fn apply_edit(grid: &mut Grid, edit: &Edit, forward: bool) {
+    match *edit {
+        Edit::Obstruction(loc) => grid.toggle_cell_obstruction(&loc).unwrap(),
+        Edit::Terrain { loc, before, after } => grid
+            .set_cell_terrain(&loc, if forward { after } else { before })
+            .unwrap(),
+        Edit::Boundry {
+            loc,
+            dir,
+            before,
+            after,
+        } => {
+            let (here, there) = if forward { (after, after) } else { before };
+
+            match here {
+                Some(boundry) => grid.add_cell_boundry_and_adjacent(&loc, dir, boundry),
+                None => grid.remove_cell_boundry_and_adjacent(&loc, dir),
+            }
+            .unwrap();
+
+            if here != there {
+                if let Ok(cell) = grid.get_mut_cell(&loc.get_loc_from_dir(&dir)) {
+                    match there {
+                        Some(boundry) => cell.add_boundry(dir.get_opposite(), boundry),
+                        None => cell.remove_boundry(&dir.get_opposite()),
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::GridEvent;
+    use rstest::{fixture, rstest};
+
+    #[fixture]
+    fn editor() -> GridEditor {
+        GridEditor::new(Grid::new(4, 4, 1))
+    }
+
+    #[rstest]
+    fn undo_and_redo_commands(mut editor: GridEditor) {
+        let loc = Location::new(1, 0, 1);
+
+        editor.apply(GridCommand::ToggleObstruction(loc)).unwrap();
+        editor
+            .apply(GridCommand::AddBoundry(loc, Direction::E, Boundry::Full))
+            .unwrap();
+
+        assert!(editor.undo());
+        assert_eq!(
+            editor.grid().boundry_between(&loc, &Location::new(2, 0, 1)),
+            Ok(None)
+        );
+        assert!(editor.grid().get_ref_cell(&loc).unwrap().is_obstructed);
+
+        assert!(editor.undo());
+        assert_eq!(editor.grid(), &Grid::new(4, 4, 1));
+        assert!(!editor.undo());
+
+        assert!(editor.redo());
+        assert!(editor.redo());
+        assert!(!editor.redo());
+        assert_eq!(
+            editor.grid().boundry_between(&loc, &Location::new(2, 0, 1)),
+            Ok(Some(Boundry::Full))
+        );
+    }
+
+    #[rstest]
+    fn undo_restores_replaced_boundries(mut editor: GridEditor) {
+        let loc = Location::new(1, 0, 1);
+
+        editor
+            .apply(GridCommand::AddBoundry(loc, Direction::S, Boundry::Half))
+            .unwrap();
+        editor
+            .apply(GridCommand::AddBoundry(loc, Direction::S, Boundry::Full))
+            .unwrap();
+        editor
+            .apply(GridCommand::RemoveBoundry(loc, Direction::S))
+            .unwrap();
+
+        let below = Location::new(1, 0, 2);
+        assert_eq!(editor.grid().boundry_between(&loc, &below), Ok(None));
+
+        editor.undo();
+        assert_eq!(
+            editor.grid().boundry_between(&loc, &below),
+            Ok(Some(Boundry::Full))
+        );
+
+        editor.undo();
+        assert_eq!(
+            editor.grid().boundry_between(&loc, &below),
+            Ok(Some(Boundry::Half))
+        );
+    }
+
+    #[rstest]
+    fn transactions_undo_as_one_step(mut editor: GridEditor) {
+        editor
+            .transaction(|editor| {
+                for x in 0..4 {
+                    editor.apply(GridCommand::ToggleObstruction(Location::new(x, 0, 0)))?;
+                }
+
+                editor.transaction(|editor| {
+                    editor.apply(GridCommand::ToggleDifficultTerrain(Location::new(0, 0, 1)))?;
+                    editor.apply(GridCommand::SetTerrain(
+                        Location::new(1, 0, 1),
+                        Terrain::Water,
+                    ))
+                })
+            })
+            .unwrap();
+
+        assert!(editor.undo());
+        assert_eq!(editor.grid(), &Grid::new(4, 4, 1));
+        assert!(!editor.can_undo());
+    }
+
+    #[rstest]
+    fn failed_transactions_roll_back(mut editor: GridEditor) {
+        let result = editor.transaction(|editor| {
+            editor.apply(GridCommand::ToggleObstruction(Location::new(0, 0, 0)))?;
+            editor.apply(GridCommand::ToggleObstruction(Location::new(9, 0, 0)))
+        });
+
+        assert_eq!(result, Err(GridError::OutOfBounds(Location::new(9, 0, 0))));
+        assert_eq!(editor.grid(), &Grid::new(4, 4, 1));
+        assert!(!editor.can_undo());
+    }
+
+    #[test]
+    fn undo_restores_one_sided_boundries() {
+        let (loc, neighbor) = (Location::new(1, 0, 1), Location::new(2, 0, 1));
+        let mut grid = Grid::new(4, 4, 1);
+        grid.get_mut_cell(&loc)
+            .unwrap()
+            .add_boundry(Direction::E, Boundry::Half);
+
+        let mut editor = GridEditor::new(grid.clone());
+        editor
+            .apply(GridCommand::AddBoundry(
+                neighbor,
+                Direction::W,
+                Boundry::Full,
+            ))
+            .unwrap();
+        editor.undo();
+
+        assert_eq!(editor.grid(), &grid);
+    }
+
+    #[test]
+    fn boundry_edits_emit_boundry_events() {
+        let (loc, neighbor) = (Location::new(1, 0, 1), Location::new(2, 0, 1));
+        let mut grid = Grid::new(4, 4, 1);
+        grid.record_events(true);
+
+        let mut editor = GridEditor::new(grid);
+        editor
+            .apply(GridCommand::AddBoundry(loc, Direction::E, Boundry::Half))
+            .unwrap();
+        editor.undo();
+        editor.redo();
+
+        let mut grid = editor.into_grid();
+        assert_eq!(
+            grid.drain_events().collect::<Vec<GridEvent>>(),
+            vec![
+                GridEvent::BoundryAdded(loc, neighbor),
+                GridEvent::BoundryRemoved(loc, neighbor),
+                GridEvent::BoundryAdded(loc, neighbor),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn undo_restores_replaced_terrain(mut editor: GridEditor) {
+        let loc = Location::new(2, 0, 2);
+
+        editor
+            .apply(GridCommand::SetTerrain(loc, Terrain::Lava))
+            .unwrap();
+        editor
+            .apply(GridCommand::ToggleDifficultTerrain(loc))
+            .unwrap();
+        assert_eq!(
+            editor.grid().get_ref_cell(&loc).unwrap().terrain,
+            Terrain::Difficult
+        );
+
+        editor.undo();
+        assert_eq!(
+            editor.grid().get_ref_cell(&loc).unwrap().terrain,
+            Terrain::Lava
+        );
+    }
+
+    #[rstest]
+    fn new_edits_clear_redo(mut editor: GridEditor) {
+        editor
+            .apply(GridCommand::ToggleObstruction(Location::new(0, 0, 0)))
+            .unwrap();
+        editor.undo();
+        editor
+            .apply(GridCommand::ToggleDifficultTerrain(Location::new(0, 0, 0)))
+            .unwrap();
+
+        assert!(!editor.can_redo());
+    }
+
+    #[rstest]
+    fn failed_transactions_keep_redo(mut editor: GridEditor) {
+        editor
+            .apply(GridCommand::ToggleObstruction(Location::new(0, 0, 0)))
+            .unwrap();
+        editor.undo();
+
+        let result = editor.transaction(|editor| {
+            editor.apply(GridCommand::ToggleDifficultTerrain(Location::new(1, 0, 0)))?;
+            editor.apply(GridCommand::ToggleObstruction(Location::new(9, 0, 0)))
+        });
+
+        assert!(result.is_err());
+        assert!(editor.can_redo());
+
+        assert!(editor.redo());
+        assert!(
+            editor
+                .grid()
+                .get_ref_cell(&Location::new(0, 0, 0))
+                .unwrap()
+                .is_obstructed
+        );
+    }
+}
@@ -0,0 +1,64 @@
+use crate::direction::Direction;
+use crate::location::Location;
+use crate::movement::MovementMode;
+use legion::Entity;
+use std::{error::Error, fmt};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GridError {
+    OutOfBounds(Location),
+    NotAdjacent(Location, Location),
+    BlockedByWall(Location, Location),
+    Obstructed(Location),
+    InvalidAscii(usize, usize),
+    EntityNotFound(Entity),
+    DuplicateEntity(Entity, Location),
+    ModeNotAllowed(Location, Location, MovementMode),
+    NotVertical(Location, Direction),
+    OutOfMovement(usize, usize),
+    InvalidWeight(Location),
+    InvalidSize(i8, i8, i8),
+}
+
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GridError::OutOfBounds(loc) => write!(f, "{:?} is outside of the grid", loc),
+            GridError::NotAdjacent(from, to) => {
+                write!(f, "{:?} is not adjacent to {:?}", to, from)
+            }
+            GridError::BlockedByWall(from, to) => {
+                write!(f, "a wall blocks movement from {:?} to {:?}", from, to)
+            }
+            GridError::Obstructed(loc) => write!(f, "{:?} is obstructed", loc),
+            GridError::InvalidAscii(line, column) => {
+                write!(f, "invalid ascii map at line {}, column {}", line, column)
+            }
+            GridError::EntityNotFound(entity) => write!(f, "{:?} is not on the grid", entity),
+            GridError::DuplicateEntity(entity, loc) => {
+                write!(f, "{:?} is already on the grid at {:?}", entity, loc)
+            }
+            GridError::ModeNotAllowed(from, to, mode) => {
+                write!(f, "cannot move from {:?} to {:?} by {:?}", from, to, mode)
+            }
+            GridError::NotVertical(loc, dir) => {
+                write!(f, "{:?} from {:?} does not lead to another layer", dir, loc)
+            }
+            GridError::OutOfMovement(needed, remaining) => write!(
+                f,
+                "needs {} movement but only {} is left",
+                needed, remaining
+            ),
+            GridError::InvalidSize(length, width, height) => write!(
+                f,
+                "a grid cannot be {} by {} by {} cells",
+                length, width, height
+            ),
+            GridError::InvalidWeight(loc) => {
+                write!(f, "weights at {:?} must be finite and not negative", loc)
+            }
+        }
+    }
+}
+
+impl Error for GridError {}
@@ -0,0 +1,130 @@
+use crate::prelude::{Grid, Location};
+use legion::Entity;
+
+// `CellChanged` is also sent for every `get_mut_cell` call, as anything on the
+// cell may have changed through it. Boundry events carry both sides of the wall,
+// and the second one may lie outside the grid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GridEvent {
+    CellChanged(Location),
+    BoundryAdded(Location, Location),
+    BoundryRemoved(Location, Location),
+    EntityAdded(Entity, Location),
+    EntityRemoved(Entity, Location),
+    EntityMoved(Entity, Location, Location),
+}
+
+impl GridEvent {
+    pub fn locations(&self) -> Vec<Location> {
+        match *self {
+            GridEvent::CellChanged(loc)
+            | GridEvent::EntityAdded(_, loc)
+            | GridEvent::EntityRemoved(_, loc) => vec![loc],
+            GridEvent::BoundryAdded(from, to)
+            | GridEvent::BoundryRemoved(from, to)
+            | GridEvent::EntityMoved(_, from, to) => vec![from, to],
+        }
+    }
+}
+
+impl Grid {
+    // Events are only queued while recording, so grids nobody listens to do not
+    // grow an unbounded queue. Turning recording off drops any pending events.
+    pub fn record_events(&mut self, enabled: bool) {
+        match (enabled, self.events.is_some()) {
+            (true, false) => self.events = Some(vec![]),
+            (false, true) => self.events = None,
+            _ => {}
+        }
+    }
+
+    pub fn drain_events(&mut self) -> impl Iterator<Item = GridEvent> + '_ {
+        self.events.iter_mut().flat_map(|events| events.drain(..))
+    }
+
+    pub(crate) fn emit(&mut self, event: GridEvent) {
+        if let Some(events) = self.events.as_mut() {
+            events.push(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::{Boundry, Direction};
+    use legion::World;
+    use rstest::{fixture, rstest};
+
+    #[fixture]
+    fn grid() -> Grid {
+        let mut grid = Grid::new(4, 4, 1);
+        grid.record_events(true);
+        grid
+    }
+
+    #[rstest]
+    fn nothing_is_queued_until_recording(mut grid: Grid) {
+        grid.record_events(false);
+        grid.toggle_cell_obstruction(&Location::new(0, 0, 0))
+            .unwrap();
+        grid.record_events(true);
+
+        assert_eq!(grid.drain_events().count(), 0);
+    }
+
+    #[rstest]
+    fn mutations_emit_events(mut grid: Grid) {
+        let (a, b) = (Location::new(1, 0, 1), Location::new(2, 0, 1));
+        let mut world = World::default();
+        let entity = world.push(());
+
+        grid.toggle_cell_difficult_terrain(&a).unwrap();
+        grid.add_cell_boundry_and_adjacent(&a, Direction::E, Boundry::Half)
+            .unwrap();
+        grid.remove_cell_boundry_and_adjacent(&b, Direction::W)
+            .unwrap();
+        grid.add_entity(&a, entity).unwrap();
+        grid.move_entity(&entity, &b).unwrap();
+        grid.remove_entity(&entity).unwrap();
+        grid.get_mut_cell(&b).unwrap();
+
+        let events: Vec<GridEvent> = grid.drain_events().collect();
+
+        assert_eq!(
+            events,
+            vec![
+                GridEvent::CellChanged(a),
+                GridEvent::BoundryAdded(a, b),
+                GridEvent::BoundryRemoved(b, a),
+                GridEvent::EntityAdded(entity, a),
+                GridEvent::EntityMoved(entity, a, b),
+                GridEvent::EntityRemoved(entity, b),
+                GridEvent::CellChanged(b),
+            ]
+        );
+        assert_eq!(grid.drain_events().count(), 0);
+        assert_eq!(grid.boundry_between(&a, &b), Ok(None));
+    }
+
+    #[rstest]
+    fn failed_mutations_emit_nothing(mut grid: Grid) {
+        let mut world = World::default();
+        let entity = world.push(());
+        assert_eq!(
+            grid.move_entity(&entity, &Location::new(1, 0, 0)),
+            Err(crate::prelude::GridError::EntityNotFound(entity))
+        );
+        assert!(grid
+            .toggle_cell_obstruction(&Location::new(9, 0, 0))
+            .is_err());
+        assert_eq!(grid.drain_events().count(), 0);
+    }
+
+    #[test]
+    fn events_list_their_locations() {
+        let (a, b) = (Location::new(0, 0, 0), Location::new(0, 0, 1));
+        assert_eq!(GridEvent::BoundryAdded(a, b).locations(), vec![a, b]);
+        assert_eq!(GridEvent::CellChanged(a).locations(), vec![a]);
+    }
+}
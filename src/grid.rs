@@ -1,26 +1,48 @@
 use super::cell::Cell;
 use super::direction::Direction;
 use super::location::Location;
-use crate::prelude::Boundry;
-use std::collections::HashMap;
+use crate::cost::is_valid_weight;
+use crate::prelude::{
+    Boundry, Connector, GridError, GridEvent, Metric, MovementMode, Terrain, TerrainRules,
+    TerrainTable,
+};
+use legion::Entity;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
+// Cells live in a base map shared between a grid and its snapshots. Writes to a
+// shared base land in the grid's own overlay instead, so cloning only copies the
+// cells that grid has changed and never touches the others.
+//
+// `entity_index` mirrors `Cell::entities` for entities placed through the grid's
+// own entity methods; entities pushed onto cells directly are not indexed.
+//
+// `terrains` holds the rules for each terrain. Like the base it is shared with
+// snapshots, and rules that differ from the built in ones are saved with the grid
+// and compared along with its cells.
+#[derive(Clone, Debug)]
 pub struct Grid {
     pub width: i8,
     pub length: i8,
     pub height: i8,
-    cells: HashMap<Location, Cell>,
-    dirs_2d: [Direction; 8],
-    dirs_3d: [Direction; 18]
+    base: Arc<HashMap<Location, Cell>>,
+    overlay: HashMap<Location, Cell>,
+    entity_index: HashMap<Entity, Location>,
+    terrains: Arc<TerrainTable>,
+    pub(crate) events: Option<Vec<GridEvent>>,
 }
 
 impl Grid {
+    // Negative sizes make an empty grid.
     pub fn new(length: i8, width: i8, height: i8) -> Self {
-        let mut cells = HashMap::with_capacity((width as usize) * (height as usize));
+        let (length, width, height) = (length.max(0), width.max(0), height.max(0));
+        let mut cells =
+            HashMap::with_capacity((length as usize) * (width as usize) * (height as usize));
 
         for x in 0..length {
             for y in 0..height {
-                for z in 0..height {
-                    let loc = Location::new(x as i8, y as i8, z as i8);
+                for z in 0..width {
+                    let loc = Location::new(x, y, z);
                     cells.insert(loc, Cell::new_empty(loc));
                 }
             }
@@ -30,120 +52,442 @@ impl Grid {
             height,
             width,
             length,
-            cells,
-            dirs_2d: [
-                Direction::NW,
-                Direction::N,
-                Direction::NE,
-                Direction::W,
-                Direction::E,
-                Direction::SW,
-                Direction::S,
-                Direction::SE
-            ],
-            dirs_3d: [
-                Direction::UNW,
-                Direction::UN,
-                Direction::UNE,
-                Direction::UW,
-                Direction::U,
-                Direction::UE,
-                Direction::USW,
-                Direction::US,
-                Direction::USE,
-                Direction::DNW,
-                Direction::DN,
-                Direction::DNE,
-                Direction::DW,
-                Direction::D,
-                Direction::DE,
-                Direction::DSW,
-                Direction::DS,
-                Direction::DSE,
-            ]
+            base: Arc::new(cells),
+            overlay: HashMap::new(),
+            entity_index: HashMap::new(),
+            terrains: Arc::new(TerrainTable::default()),
+            events: None,
         }
     }
 
-    pub fn add_cell_boundry_and_adjacent(&mut self, loc: &Location, dir: Direction, boundry: Boundry) {
+    pub fn from_cells(
+        length: i8,
+        width: i8,
+        height: i8,
+        cells: impl IntoIterator<Item = Cell>,
+    ) -> Result<Self, GridError> {
+        if length < 0 || width < 0 || height < 0 {
+            return Err(GridError::InvalidSize(length, width, height));
+        }
+
+        let mut grid = Grid::new(length, width, height);
+
+        for cell in cells {
+            let loc = cell.loc;
+
+            let weights = cell.edge_weights.iter().flatten().map(|(_, weight)| weight);
+
+            if !weights
+                .chain(cell.weight.iter())
+                .all(|weight| is_valid_weight(*weight))
+            {
+                return Err(GridError::InvalidWeight(loc));
+            }
+
+            if let Some(dir) = cell
+                .connectors
+                .iter()
+                .flatten()
+                .map(|(dir, _)| *dir)
+                .find(|dir| !dir.is_vertical())
+            {
+                return Err(GridError::NotVertical(loc, dir));
+            }
+
+            for entity in cell.entities.iter() {
+                if let Some(other) = grid.entity_index.insert(*entity, loc) {
+                    return Err(GridError::DuplicateEntity(*entity, other));
+                }
+            }
 
-        if !self.in_bounds(&loc) { 
-            return; 
+            *grid.get_mut_cell(&loc)? = cell;
         }
 
-        let cell = self.cells.get_mut(loc).unwrap();
-        cell.add_boundry(dir.clone(), boundry.clone());
+        Ok(grid)
+    }
 
-        let opposite_dir = dir.get_opposite();
+    pub fn cells_iter(&self) -> impl Iterator<Item = &Cell> {
+        let base = self
+            .base
+            .iter()
+            .filter(move |(loc, _)| !self.overlay.contains_key(loc))
+            .map(|(_, cell)| cell);
 
-        let opp_loc = cell.loc.get_loc_from_dir(&opposite_dir);
+        self.overlay.values().chain(base)
+    }
 
-        if self.in_bounds(&opp_loc) {
-            return;
+    // Snapshots start without the event queue, so what-if edits are not reported.
+    pub fn snapshot(&self) -> Grid {
+        Grid {
+            events: None,
+            ..self.clone()
         }
+    }
+
+    pub fn add_cell_boundry_and_adjacent(
+        &mut self,
+        loc: &Location,
+        dir: Direction,
+        boundry: Boundry,
+    ) -> Result<(), GridError> {
+        self.cell_mut(loc)?.add_boundry(dir, boundry);
+
+        let neighbor = loc.get_loc_from_dir(&dir);
+
+        if let Ok(other_cell) = self.cell_mut(&neighbor) {
+            other_cell.add_boundry(dir.get_opposite(), boundry);
+        }
+
+        self.emit(GridEvent::BoundryAdded(*loc, neighbor));
 
-        let other_cell = self.cells.get_mut(&opp_loc).unwrap();
-        other_cell.add_boundry(dir, boundry);
+        Ok(())
     }
 
-    pub fn get_mut_cell(&mut self, loc: &Location) -> Option<&mut Cell> {
+    pub fn remove_cell_boundry_and_adjacent(
+        &mut self,
+        loc: &Location,
+        dir: Direction,
+    ) -> Result<(), GridError> {
+        self.cell_mut(loc)?.remove_boundry(&dir);
 
-        if self.in_bounds(loc) {
-            Some(self.cells.get_mut(loc).unwrap())
-        } else {
-            None
+        let neighbor = loc.get_loc_from_dir(&dir);
+
+        if let Ok(other_cell) = self.cell_mut(&neighbor) {
+            other_cell.remove_boundry(&dir.get_opposite());
+        }
+
+        self.emit(GridEvent::BoundryRemoved(*loc, neighbor));
+
+        Ok(())
+    }
+
+    // Stairs and ramps only lead up or down, and both of their ends must be on the
+    // grid.
+    pub fn add_cell_connector_and_adjacent(
+        &mut self,
+        loc: &Location,
+        dir: Direction,
+        connector: Connector,
+    ) -> Result<(), GridError> {
+        if !dir.is_vertical() {
+            return Err(GridError::NotVertical(*loc, dir));
+        }
+
+        let neighbor = loc.get_loc_from_dir(&dir);
+        self.get_ref_cell(&neighbor)?;
+
+        self.cell_mut(loc)?.add_connector(dir, connector)?;
+        self.cell_mut(&neighbor)?
+            .add_connector(dir.get_opposite(), connector)?;
+
+        self.emit(GridEvent::CellChanged(*loc));
+        self.emit(GridEvent::CellChanged(neighbor));
+
+        Ok(())
+    }
+
+    pub fn remove_cell_connector_and_adjacent(
+        &mut self,
+        loc: &Location,
+        dir: Direction,
+    ) -> Result<(), GridError> {
+        self.cell_mut(loc)?.remove_connector(&dir);
+        self.emit(GridEvent::CellChanged(*loc));
+
+        let neighbor = loc.get_loc_from_dir(&dir);
+
+        if let Ok(other_cell) = self.cell_mut(&neighbor) {
+            other_cell.remove_connector(&dir.get_opposite());
+            self.emit(GridEvent::CellChanged(neighbor));
         }
+
+        Ok(())
     }
 
-    pub fn get_ref_cell(&self, loc: &Location) -> Option<&Cell> {
+    pub fn connector_between(
+        &self,
+        current_loc: &Location,
+        new_loc: &Location,
+    ) -> Result<Option<Connector>, GridError> {
+        let from_current = self
+            .get_ref_cell(current_loc)?
+            .get_connector_to_neighbor(new_loc)?;
+        let from_new = self
+            .get_ref_cell(new_loc)?
+            .get_connector_to_neighbor(current_loc)?;
 
-        if self.in_bounds(loc) {
-            Some(self.cells.get(loc).unwrap())
+        Ok(from_current.or(from_new))
+    }
+
+    // Adds a wall on the `dir` side of `loc`, going through the neighbor on the
+    // other side when `loc` itself lies just outside the grid.
+    pub(crate) fn add_edge_boundry(
+        &mut self,
+        loc: Location,
+        dir: Direction,
+        boundry: Boundry,
+    ) -> Result<(), GridError> {
+        if self.in_bounds(&loc) {
+            self.add_cell_boundry_and_adjacent(&loc, dir, boundry)
         } else {
-            None
+            self.add_cell_boundry_and_adjacent(
+                &loc.get_loc_from_dir(&dir),
+                dir.get_opposite(),
+                boundry,
+            )
         }
     }
 
-    pub fn toggle_cell_difficult_terrain(&mut self, loc: &Location) {
-        let cell = self.cells.get_mut(loc).unwrap();
-        cell.is_difficult_terrain = !cell.is_difficult_terrain;
+    // Callers may change anything on the cell, so it is reported as changed.
+    pub fn get_mut_cell(&mut self, loc: &Location) -> Result<&mut Cell, GridError> {
+        self.get_ref_cell(loc)?;
+        self.emit(GridEvent::CellChanged(*loc));
+
+        self.cell_mut(loc)
     }
 
-    pub fn toggle_cell_obstruction(&mut self, loc: &Location) {
-        let cell = self.cells.get_mut(loc).unwrap();
+    fn cell_mut(&mut self, loc: &Location) -> Result<&mut Cell, GridError> {
+        if !self.overlay.contains_key(loc) {
+            if Arc::get_mut(&mut self.base).is_some() {
+                return Arc::get_mut(&mut self.base)
+                    .unwrap()
+                    .get_mut(loc)
+                    .ok_or(GridError::OutOfBounds(*loc));
+            }
+
+            let cell = self.base.get(loc).ok_or(GridError::OutOfBounds(*loc))?;
+            self.overlay.insert(*loc, cell.clone());
+        }
+
+        Ok(self.overlay.get_mut(loc).unwrap())
+    }
+
+    pub fn get_ref_cell(&self, loc: &Location) -> Result<&Cell, GridError> {
+        self.overlay
+            .get(loc)
+            .or_else(|| self.base.get(loc))
+            .ok_or(GridError::OutOfBounds(*loc))
+    }
+
+    // Switches between difficult terrain and floor, replacing any other terrain.
+    pub fn toggle_cell_difficult_terrain(&mut self, loc: &Location) -> Result<(), GridError> {
+        let terrain = match self.get_ref_cell(loc)?.terrain {
+            Terrain::Difficult => Terrain::Floor,
+            _ => Terrain::Difficult,
+        };
+
+        self.set_cell_terrain(loc, terrain)
+    }
+
+    pub fn set_cell_terrain(&mut self, loc: &Location, terrain: Terrain) -> Result<(), GridError> {
+        self.cell_mut(loc)?.terrain = terrain;
+        self.emit(GridEvent::CellChanged(*loc));
+        Ok(())
+    }
+
+    pub fn terrain_table(&self) -> &TerrainTable {
+        &self.terrains
+    }
+
+    pub fn define_terrain(&mut self, terrain: Terrain, rules: TerrainRules) {
+        Arc::make_mut(&mut self.terrains).define(terrain, rules);
+    }
+
+    pub fn terrain_rules(&self, loc: &Location) -> Result<&TerrainRules, GridError> {
+        Ok(self.terrains.get(&self.get_ref_cell(loc)?.terrain))
+    }
+
+    pub fn stops_movement(&self, loc: &Location, mode: MovementMode) -> bool {
+        self.terrain_rules(loc)
+            .is_ok_and(|rules| rules.stops_movement(mode))
+    }
+
+    // Weights replace what the terrain of a cell, or the walls along an edge, cost
+    // in the weighted searches. Edge weights are kept on both sides of the edge.
+    pub fn set_cell_weight(
+        &mut self,
+        loc: &Location,
+        weight: Option<f64>,
+    ) -> Result<(), GridError> {
+        if !weight.is_none_or(is_valid_weight) {
+            return Err(GridError::InvalidWeight(*loc));
+        }
+
+        self.cell_mut(loc)?.weight = weight;
+        self.emit(GridEvent::CellChanged(*loc));
+        Ok(())
+    }
+
+    pub fn set_edge_weight(
+        &mut self,
+        loc: &Location,
+        dir: Direction,
+        weight: Option<f64>,
+    ) -> Result<(), GridError> {
+        if !weight.is_none_or(is_valid_weight) {
+            return Err(GridError::InvalidWeight(*loc));
+        }
+
+        self.cell_mut(loc)?.set_edge_weight(dir, weight);
+        self.emit(GridEvent::CellChanged(*loc));
+
+        let neighbor = loc.get_loc_from_dir(&dir);
+
+        if let Ok(other_cell) = self.cell_mut(&neighbor) {
+            other_cell.set_edge_weight(dir.get_opposite(), weight);
+            self.emit(GridEvent::CellChanged(neighbor));
+        }
+
+        Ok(())
+    }
+
+    pub fn edge_weight_between(
+        &self,
+        current_loc: &Location,
+        new_loc: &Location,
+    ) -> Result<Option<f64>, GridError> {
+        let from_current = self
+            .get_ref_cell(current_loc)?
+            .get_edge_weight_to_neighbor(new_loc)?;
+        let from_new = self
+            .get_ref_cell(new_loc)?
+            .get_edge_weight_to_neighbor(current_loc)?;
+
+        Ok(match (from_current, from_new) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (weight, None) | (None, weight) => weight,
+        })
+    }
+
+    pub fn toggle_cell_obstruction(&mut self, loc: &Location) -> Result<(), GridError> {
+        let cell = self.cell_mut(loc)?;
         cell.is_obstructed = !cell.is_obstructed;
+        self.emit(GridEvent::CellChanged(*loc));
+        Ok(())
+    }
+
+    pub fn add_entity(&mut self, loc: &Location, entity: Entity) -> Result<(), GridError> {
+        if let Some(other) = self.entity_index.get(&entity) {
+            return Err(GridError::DuplicateEntity(entity, *other));
+        }
+
+        self.cell_mut(loc)?.add_entity(entity);
+        self.entity_index.insert(entity, *loc);
+        self.emit(GridEvent::EntityAdded(entity, *loc));
+        Ok(())
+    }
+
+    pub fn remove_entity(&mut self, entity: &Entity) -> Result<Location, GridError> {
+        let loc = self
+            .entity_index
+            .remove(entity)
+            .ok_or(GridError::EntityNotFound(*entity))?;
+
+        self.cell_mut(&loc)?.remove_entity(entity);
+        self.emit(GridEvent::EntityRemoved(*entity, loc));
+        Ok(loc)
+    }
+
+    // Either both cells and the index change, or nothing does.
+    pub fn move_entity(&mut self, entity: &Entity, to: &Location) -> Result<(), GridError> {
+        let from = self
+            .entity_location(entity)
+            .ok_or(GridError::EntityNotFound(*entity))?;
+
+        self.get_ref_cell(to)?;
+
+        self.cell_mut(&from)?.remove_entity(entity);
+        self.cell_mut(to)?.add_entity(*entity);
+        self.entity_index.insert(*entity, *to);
+        self.emit(GridEvent::EntityMoved(*entity, from, *to));
+        Ok(())
+    }
+
+    pub fn entity_location(&self, entity: &Entity) -> Option<Location> {
+        self.entity_index.get(entity).copied()
+    }
+
+    pub fn entities_in_radius(
+        &self,
+        origin: &Location,
+        radius: f32,
+        metric: Metric,
+    ) -> Result<HashSet<Entity>, GridError> {
+        self.get_ref_cell(origin)?;
+
+        Ok(self
+            .entity_index
+            .iter()
+            .filter(|(_, loc)| loc.y == origin.y && origin.distance_2d(loc, metric) <= radius)
+            .map(|(entity, _)| *entity)
+            .collect())
+    }
+
+    // Entities inside the box spanned by two opposite corners, both included.
+    pub fn entities_in_rect(&self, corner: &Location, other_corner: &Location) -> HashSet<Entity> {
+        let (min, max) = (
+            Location::new(
+                corner.x.min(other_corner.x),
+                corner.y.min(other_corner.y),
+                corner.z.min(other_corner.z),
+            ),
+            Location::new(
+                corner.x.max(other_corner.x),
+                corner.y.max(other_corner.y),
+                corner.z.max(other_corner.z),
+            ),
+        );
+
+        self.entity_index
+            .iter()
+            .filter(|(_, loc)| {
+                (min.x..=max.x).contains(&loc.x)
+                    && (min.y..=max.y).contains(&loc.y)
+                    && (min.z..=max.z).contains(&loc.z)
+            })
+            .map(|(entity, _)| *entity)
+            .collect()
     }
 
     pub fn try_get_passable_neighbor(
         &self,
         current: &Location,
         direction: &Direction,
-    ) -> Option<Location> {
+    ) -> Result<Option<Location>, GridError> {
+        self.get_ref_cell(current)?;
+
+        let new_loc = current.get_loc_from_dir(direction);
+
+        if !self.in_bounds(&new_loc) {
+            return Ok(None);
+        }
+
         match *direction {
             Direction::NW => self.passable_diagonal_neighbor(
-                &current,
-                current.get_loc_from_dir(direction),
+                current,
+                new_loc,
                 &current.get_loc_from_dir(&Direction::W),
                 &current.get_loc_from_dir(&Direction::N),
             ),
             Direction::NE => self.passable_diagonal_neighbor(
-                &current,
-                current.get_loc_from_dir(direction),
+                current,
+                new_loc,
                 &current.get_loc_from_dir(&Direction::E),
                 &current.get_loc_from_dir(&Direction::N),
             ),
             Direction::SW => self.passable_diagonal_neighbor(
-                &current,
-                current.get_loc_from_dir(direction),
+                current,
+                new_loc,
                 &current.get_loc_from_dir(&Direction::W),
                 &current.get_loc_from_dir(&Direction::S),
             ),
             Direction::SE => self.passable_diagonal_neighbor(
-                &current,
-                current.get_loc_from_dir(direction),
+                current,
+                new_loc,
                 &current.get_loc_from_dir(&Direction::E),
                 &current.get_loc_from_dir(&Direction::S),
             ),
-            _ => self.passable_cardinal_neighbor(&current, current.get_loc_from_dir(direction)),
+            _ => self.passable_cardinal_neighbor(current, new_loc),
         }
     }
 
@@ -156,44 +500,143 @@ impl Grid {
             && loc.z < self.width
     }
 
-    pub fn cost_2d(&self, current_loc: &Location, new_loc: &Location) -> usize {
-        let boundry_cost = match current_loc.get_dir_from_loc(new_loc) {
+    pub fn cells_within(
+        &self,
+        origin: &Location,
+        radius: f32,
+        metric: Metric,
+    ) -> Result<HashSet<Location>, GridError> {
+        self.get_ref_cell(origin)?;
+        let reach = radius.floor() as i8;
+
+        Ok(self
+            .cells_in_range(origin, reach, 0)
+            .filter(|loc| origin.distance_2d(loc, metric) <= radius)
+            .collect())
+    }
+
+    pub fn cells_within_3d(
+        &self,
+        origin: &Location,
+        radius: f32,
+        metric: Metric,
+    ) -> Result<HashSet<Location>, GridError> {
+        self.get_ref_cell(origin)?;
+        let reach = radius.floor() as i8;
+
+        Ok(self
+            .cells_in_range(origin, reach, reach)
+            .filter(|loc| origin.distance_3d(loc, metric) <= radius)
+            .collect())
+    }
+
+    fn cells_in_range(
+        &self,
+        origin: &Location,
+        reach: i8,
+        vertical_reach: i8,
+    ) -> impl Iterator<Item = Location> + '_ {
+        let origin = *origin;
+
+        (origin.x.saturating_sub(reach)..=origin.x.saturating_add(reach))
+            .flat_map(move |x| {
+                (origin.y.saturating_sub(vertical_reach)..=origin.y.saturating_add(vertical_reach))
+                    .flat_map(move |y| {
+                        (origin.z.saturating_sub(reach)..=origin.z.saturating_add(reach))
+                            .map(move |z| Location::new(x, y, z))
+                    })
+            })
+            .filter(move |loc| self.in_bounds(loc))
+    }
+
+    pub fn boundry_between(
+        &self,
+        current_loc: &Location,
+        new_loc: &Location,
+    ) -> Result<Option<Boundry>, GridError> {
+        let from_current = self
+            .get_ref_cell(current_loc)?
+            .get_boundry_to_neighbor(new_loc)?;
+        let from_new = self
+            .get_ref_cell(new_loc)?
+            .get_boundry_to_neighbor(current_loc)?;
+
+        Ok(match (from_current, from_new) {
+            (Some(Boundry::Full), _) | (_, Some(Boundry::Full)) => Some(Boundry::Full),
+            (Some(Boundry::Half), _) | (_, Some(Boundry::Half)) => Some(Boundry::Half),
+            _ => None,
+        })
+    }
+
+    pub fn cost_2d(&self, current_loc: &Location, new_loc: &Location) -> Result<usize, GridError> {
+        self.cost_for_mode(current_loc, new_loc, MovementMode::Walk)
+    }
+
+    // `boundry_cost_2d` for burrowers, who dig through obstructions but not walls.
+    pub(crate) fn wall_cost_2d(
+        &self,
+        current_loc: &Location,
+        new_loc: &Location,
+    ) -> Result<usize, GridError> {
+        let offset = current_loc.get_dir_from_loc(new_loc)?.offset();
+
+        match (offset.x, offset.z) {
+            (0, _) | (_, 0) => self.cardinal_neighbor_boundry_cost_2d(current_loc, *new_loc),
+            (x, z) => self.diaganol_neighbor_boundry_cost(
+                current_loc,
+                *new_loc,
+                &Location::new(current_loc.x + x, current_loc.y, current_loc.z),
+                &Location::new(current_loc.x, current_loc.y, current_loc.z + z),
+            ),
+        }
+    }
+
+    // `cost_2d` without terrain: what crossing the walls between the two cells costs.
+    pub(crate) fn boundry_cost_2d(
+        &self,
+        current_loc: &Location,
+        new_loc: &Location,
+    ) -> Result<usize, GridError> {
+        let neighbor_cell = self.get_ref_cell(new_loc)?;
+        let dir = current_loc.get_dir_from_loc(new_loc)?;
+
+        if neighbor_cell.is_obstructed {
+            return Err(GridError::Obstructed(*new_loc));
+        }
+
+        if self.try_get_passable_neighbor(current_loc, &dir)?.is_none() {
+            return Err(GridError::BlockedByWall(*current_loc, *new_loc));
+        }
+
+        let boundry_cost = match dir {
             Direction::NW => self.diaganol_neighbor_boundry_cost(
                 current_loc,
                 *new_loc,
                 &current_loc.get_loc_from_dir(&Direction::N),
                 &current_loc.get_loc_from_dir(&Direction::W),
-            ),
+            )?,
             Direction::NE => self.diaganol_neighbor_boundry_cost(
                 current_loc,
                 *new_loc,
                 &current_loc.get_loc_from_dir(&Direction::N),
                 &current_loc.get_loc_from_dir(&Direction::E),
-            ),
+            )?,
             Direction::SW => self.diaganol_neighbor_boundry_cost(
                 current_loc,
                 *new_loc,
                 &current_loc.get_loc_from_dir(&Direction::S),
                 &current_loc.get_loc_from_dir(&Direction::W),
-            ),
+            )?,
             Direction::SE => self.diaganol_neighbor_boundry_cost(
                 current_loc,
                 *new_loc,
                 &current_loc.get_loc_from_dir(&Direction::S),
-                &current_loc.get_loc_from_dir(&Direction::W),
-            ),
-            _ => self.cardinal_neighbor_boundry_cost_2d(current_loc, *new_loc),
+                &current_loc.get_loc_from_dir(&Direction::E),
+            )?,
+            _ => self.cardinal_neighbor_boundry_cost_2d(current_loc, *new_loc)?,
         };
 
-        let neighbor_cell = self.cells.get(new_loc).unwrap();
-
-        match boundry_cost {
-            1 => match neighbor_cell.is_difficult_terrain {
-                true => 2,
-                false => 1,
-            },
-            _ => 2,
-        }
+        Ok(boundry_cost)
     }
 
     pub fn visitable_neighbors_2d_iter(
@@ -201,31 +644,37 @@ impl Grid {
         current_loc: Location,
     ) -> impl Iterator<Item = Location> + '_ {
         self.in_bounds_neighbors_2d_iter(current_loc)
-            .filter_map(move |direction| self.try_get_passable_neighbor(&current_loc, direction))
+            .filter_map(move |direction| {
+                self.try_get_passable_neighbor(&current_loc, &direction)
+                    .ok()
+                    .flatten()
+            })
     }
 
     pub fn neighbors_2d_iter(&self, current_loc: Location) -> impl Iterator<Item = Location> + '_ {
         self.in_bounds_neighbors_2d_iter(current_loc)
-            .map(move |direction| current_loc.get_loc_from_dir(direction))
+            .map(move |direction| current_loc.get_loc_from_dir(&direction))
+    }
+
+    pub fn neighbors_3d_iter(&self, current_loc: Location) -> impl Iterator<Item = Location> + '_ {
+        self.in_bounds_neighbors_3d_iter(current_loc)
+            .map(move |direction| current_loc.get_loc_from_dir(&direction))
     }
 
     fn in_bounds_neighbors_2d_iter(
         &self,
         current_loc: Location,
-    ) -> impl Iterator<Item = &Direction> {
-        self.dirs_2d
-            .iter()
-            .filter(move |direction| self.in_bounds(&current_loc.get_loc_from_dir(*direction)))
+    ) -> impl Iterator<Item = Direction> + '_ {
+        Direction::all_2d()
+            .filter(move |direction| self.in_bounds(&current_loc.get_loc_from_dir(direction)))
     }
 
     fn in_bounds_neighbors_3d_iter(
         &self,
-        current_loc: Location
-    ) -> impl Iterator<Item = &Direction> {
-        self.dirs_2d
-        .iter()
-        .chain(self.dirs_3d.iter())
-        .filter(move |direction| self.in_bounds(&current_loc.get_loc_from_dir(*direction)))
+        current_loc: Location,
+    ) -> impl Iterator<Item = Direction> + '_ {
+        Direction::all_3d()
+            .filter(move |direction| self.in_bounds(&current_loc.get_loc_from_dir(direction)))
     }
 
     fn passable_diagonal_neighbor(
@@ -234,16 +683,16 @@ impl Grid {
         diag_loc: Location,
         cardinal_1: &Location,
         cardinal_2: &Location,
-    ) -> Option<Location> {
-        if self.is_passable_to_neighbor_2d(current_loc, cardinal_1)
-            && self.is_passable_to_neighbor_2d(current_loc, cardinal_2)
-            && self.is_passable_to_neighbor_2d(cardinal_1, &diag_loc)
-            && self.is_passable_to_neighbor_2d(cardinal_2, &diag_loc)
-            && self.is_passable_to_neighbor_2d(current_loc, &diag_loc)
+    ) -> Result<Option<Location>, GridError> {
+        if self.is_passable_to_neighbor_2d(current_loc, cardinal_1)?
+            && self.is_passable_to_neighbor_2d(current_loc, cardinal_2)?
+            && self.is_passable_to_neighbor_2d(cardinal_1, &diag_loc)?
+            && self.is_passable_to_neighbor_2d(cardinal_2, &diag_loc)?
+            && self.is_passable_to_neighbor_2d(current_loc, &diag_loc)?
         {
-            Some(diag_loc)
+            Ok(Some(diag_loc))
         } else {
-            None
+            Ok(None)
         }
     }
 
@@ -251,26 +700,29 @@ impl Grid {
         &self,
         current_loc: &Location,
         cardinal_loc: Location,
-    ) -> Option<Location> {
-        if self.is_passable_to_neighbor_2d(current_loc, &cardinal_loc) {
-            Some(cardinal_loc)
+    ) -> Result<Option<Location>, GridError> {
+        if self.is_passable_to_neighbor_2d(current_loc, &cardinal_loc)? {
+            Ok(Some(cardinal_loc))
         } else {
-            None
+            Ok(None)
         }
     }
 
-    // TODO: Proper error handling
-    fn is_passable_to_neighbor_2d(&self, current_loc: &Location, new_loc: &Location) -> bool {
-        let neighbor_cell = self.cells.get(new_loc).unwrap();
+    fn is_passable_to_neighbor_2d(
+        &self,
+        current_loc: &Location,
+        new_loc: &Location,
+    ) -> Result<bool, GridError> {
+        let neighbor_cell = self.get_ref_cell(new_loc)?;
 
         if neighbor_cell.is_obstructed {
-            return false;
+            return Ok(false);
         }
 
-        let passable_from_new_loc = neighbor_cell.is_passable_to_neighbor(current_loc);
-        let current_cell = self.cells.get(current_loc).unwrap();
-        let passable_from_current_loc = current_cell.is_passable_to_neighbor(new_loc);
-        passable_from_current_loc && passable_from_new_loc
+        let passable_from_new_loc = neighbor_cell.is_passable_to_neighbor(current_loc)?;
+        let current_cell = self.get_ref_cell(current_loc)?;
+        let passable_from_current_loc = current_cell.is_passable_to_neighbor(new_loc)?;
+        Ok(passable_from_current_loc && passable_from_new_loc)
     }
 
     fn diaganol_neighbor_boundry_cost(
@@ -279,16 +731,16 @@ impl Grid {
         diag_loc: Location,
         cardinal_1: &Location,
         cardinal_2: &Location,
-    ) -> usize {
+    ) -> Result<usize, GridError> {
         match (
-            self.boundry_movement_cost_2d(current_loc, cardinal_1),
-            self.boundry_movement_cost_2d(current_loc, cardinal_2),
-            self.boundry_movement_cost_2d(cardinal_1, &diag_loc),
-            self.boundry_movement_cost_2d(cardinal_2, &diag_loc),
-            self.boundry_movement_cost_2d(current_loc, &diag_loc),
+            self.boundry_movement_cost_2d(current_loc, cardinal_1)?,
+            self.boundry_movement_cost_2d(current_loc, cardinal_2)?,
+            self.boundry_movement_cost_2d(cardinal_1, &diag_loc)?,
+            self.boundry_movement_cost_2d(cardinal_2, &diag_loc)?,
+            self.boundry_movement_cost_2d(current_loc, &diag_loc)?,
         ) {
-            (1, 1, 1, 1, 1) => 1,
-            _ => 2,
+            (1, 1, 1, 1, 1) => Ok(1),
+            _ => Ok(2),
         }
     }
 
@@ -296,25 +748,39 @@ impl Grid {
         &self,
         current_loc: &Location,
         cardinal_loc: Location,
-    ) -> usize {
+    ) -> Result<usize, GridError> {
         self.boundry_movement_cost_2d(current_loc, &cardinal_loc)
     }
 
-    fn boundry_movement_cost_2d(&self, current_loc: &Location, new_loc: &Location) -> usize {
-        let neighbor_cell = self.cells.get(new_loc).unwrap();
-        let current_to_new_cost = neighbor_cell.cost_to_neighbor(current_loc);
+    fn boundry_movement_cost_2d(
+        &self,
+        current_loc: &Location,
+        new_loc: &Location,
+    ) -> Result<usize, GridError> {
+        let neighbor_cell = self.get_ref_cell(new_loc)?;
+        let current_to_new_cost = neighbor_cell.cost_to_neighbor(current_loc)?;
 
-        // TODO: May change as in incpororate heights and ramps
-        let current_cell = self.cells.get(current_loc).unwrap();
-        let new_to_current_cost = current_cell.cost_to_neighbor(new_loc);
+        let current_cell = self.get_ref_cell(current_loc)?;
+        let new_to_current_cost = current_cell.cost_to_neighbor(new_loc)?;
 
         match (current_to_new_cost, new_to_current_cost) {
-            (1, 1) => 1,
-            _ => 2,
+            (1, 1) => Ok(1),
+            _ => Ok(2),
         }
     }
 }
 
+impl PartialEq for Grid {
+    fn eq(&self, other: &Self) -> bool {
+        (self.length, self.width, self.height) == (other.length, other.width, other.height)
+            && self.terrains == other.terrains
+            && self
+                .base
+                .keys()
+                .all(|loc| self.get_ref_cell(loc) == other.get_ref_cell(loc))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::prelude::Boundry;
@@ -322,20 +788,37 @@ mod tests {
     use super::*;
     use rstest::{fixture, rstest};
 
+    #[test]
+    fn negative_sizes_make_empty_grids() {
+        let grid = Grid::new(-1, 1, 1);
+
+        assert_eq!((grid.length, grid.width, grid.height), (0, 1, 1));
+        assert_eq!(grid.cells_iter().count(), 0);
+    }
+
     #[fixture]
     fn grid() -> Grid {
         let mut grid = Grid::new(6, 5, 5);
-        grid.add_cell_boundry_and_adjacent(&Location::new(2, 0, 1), Direction::S, Boundry::Full);
-        grid.add_cell_boundry_and_adjacent(&Location::new(3, 0, 1), Direction::S, Boundry::Full);
-        grid.add_cell_boundry_and_adjacent(&Location::new(1, 0, 2), Direction::E, Boundry::Full);
-        grid.add_cell_boundry_and_adjacent(&Location::new(4, 0, 2), Direction::SE, Boundry::Full);
-        grid.add_cell_boundry_and_adjacent(&Location::new(3, 0, 2), Direction::S, Boundry::Half);
+        grid.add_cell_boundry_and_adjacent(&Location::new(2, 0, 1), Direction::S, Boundry::Full)
+            .unwrap();
+        grid.add_cell_boundry_and_adjacent(&Location::new(3, 0, 1), Direction::S, Boundry::Full)
+            .unwrap();
+        grid.add_cell_boundry_and_adjacent(&Location::new(1, 0, 2), Direction::E, Boundry::Full)
+            .unwrap();
+        grid.add_cell_boundry_and_adjacent(&Location::new(4, 0, 2), Direction::SE, Boundry::Full)
+            .unwrap();
+        grid.add_cell_boundry_and_adjacent(&Location::new(3, 0, 2), Direction::S, Boundry::Half)
+            .unwrap();
 
-        grid.toggle_cell_difficult_terrain(&Location::new(2, 0, 3));
-        grid.toggle_cell_difficult_terrain(&Location::new(2, 0, 4));
-        grid.toggle_cell_difficult_terrain(&Location::new(3, 0, 4));
+        grid.toggle_cell_difficult_terrain(&Location::new(2, 0, 3))
+            .unwrap();
+        grid.toggle_cell_difficult_terrain(&Location::new(2, 0, 4))
+            .unwrap();
+        grid.toggle_cell_difficult_terrain(&Location::new(3, 0, 4))
+            .unwrap();
 
-        grid.toggle_cell_obstruction(&Location::new(4, 0, 1));
+        grid.toggle_cell_obstruction(&Location::new(4, 0, 1))
+            .unwrap();
 
         grid
     }
@@ -360,23 +843,187 @@ mod tests {
         #[case] expected: Option<Location>,
     ) {
         let result = grid.try_get_passable_neighbor(start_loc, dir);
-        assert_eq!(result, expected);
+        assert_eq!(result, Ok(expected));
     }
 
     #[rstest]
-    #[case(&Location::new(3, 0, 2), &Location::new(3, 0, 3), 2)]
-    #[case(&Location::new(3, 0, 2), &Location::new(2, 0, 3), 2)]
-    #[case(&Location::new(3, 0, 2), &Location::new(4, 0, 3), 2)]
+    #[case(Metric::Chebyshev, 1.0, 9)]
+    #[case(Metric::Manhattan, 1.0, 5)]
+    #[case(Metric::Euclidean, 1.5, 9)]
+    #[case(Metric::Alternating, 2.0, 21)]
+    fn cells_within_tests(
+        grid: Grid,
+        #[case] metric: Metric,
+        #[case] radius: f32,
+        #[case] expected: usize,
+    ) {
+        let result = grid
+            .cells_within(&Location::new(2, 0, 2), radius, metric)
+            .unwrap();
+        assert_eq!(result.len(), expected);
+    }
+
+    #[rstest]
+    fn cells_within_3d_reaches_other_layers(grid: Grid) {
+        let result = grid
+            .cells_within_3d(&Location::new(0, 0, 0), 1.0, Metric::Chebyshev)
+            .unwrap();
+        assert_eq!(result.len(), 8);
+    }
+
+    #[rstest]
+    #[case(&Location::new(3, 0, 2), &Location::new(3, 0, 3), Ok(2))]
+    #[case(&Location::new(3, 0, 2), &Location::new(2, 0, 3), Ok(2))]
+    #[case(&Location::new(3, 0, 2), &Location::new(4, 0, 3), Ok(2))]
+    #[case(&Location::new(0, 0, 0), &Location::new(1, 0, 1), Ok(1))]
+    #[case(&Location::new(5, 0, 4), &Location::new(6, 0, 4), Err(GridError::OutOfBounds(Location::new(6, 0, 4))))]
+    #[case(&Location::new(0, 0, 0), &Location::new(2, 0, 0), Err(GridError::NotAdjacent(Location::new(0, 0, 0), Location::new(2, 0, 0))))]
+    #[case(&Location::new(2, 0, 1), &Location::new(2, 0, 2), Err(GridError::BlockedByWall(Location::new(2, 0, 1), Location::new(2, 0, 2))))]
+    #[case(&Location::new(4, 0, 2), &Location::new(4, 0, 1), Err(GridError::Obstructed(Location::new(4, 0, 1))))]
     fn movement_cost_2d_test(
         grid: Grid,
         #[case] start: &Location,
         #[case] end: &Location,
-        #[case] expected: usize,
+        #[case] expected: Result<usize, GridError>,
     ) {
         let result = grid.cost_2d(start, end);
         assert_eq!(result, expected);
     }
 
+    #[rstest]
+    fn out_of_bounds_mutations_are_errors(mut grid: Grid) {
+        let loc = Location::new(6, 0, 0);
+
+        assert_eq!(
+            grid.toggle_cell_obstruction(&loc),
+            Err(GridError::OutOfBounds(loc))
+        );
+        assert_eq!(
+            grid.toggle_cell_difficult_terrain(&loc),
+            Err(GridError::OutOfBounds(loc))
+        );
+        assert_eq!(
+            grid.add_cell_boundry_and_adjacent(&loc, Direction::N, Boundry::Full),
+            Err(GridError::OutOfBounds(loc))
+        );
+    }
+
+    #[rstest]
+    fn snapshots_leave_the_base_untouched(grid: Grid) {
+        let door = Location::new(0, 0, 1);
+        let mut snapshot = grid.snapshot();
+
+        snapshot
+            .add_cell_boundry_and_adjacent(&door, Direction::N, Boundry::Full)
+            .unwrap();
+
+        assert_eq!(
+            grid.boundry_between(&door, &Location::new(0, 0, 0)),
+            Ok(None)
+        );
+        assert_eq!(
+            snapshot.boundry_between(&door, &Location::new(0, 0, 0)),
+            Ok(Some(Boundry::Full))
+        );
+        assert_ne!(grid, snapshot);
+        assert_eq!(snapshot.cells_iter().count(), grid.cells_iter().count());
+    }
+
+    #[rstest]
+    fn base_edits_do_not_reach_snapshots(mut grid: Grid) {
+        let snapshot = grid.snapshot();
+        let loc = Location::new(0, 0, 0);
+
+        grid.toggle_cell_obstruction(&loc).unwrap();
+
+        assert!(grid.get_ref_cell(&loc).unwrap().is_obstructed);
+        assert!(!snapshot.get_ref_cell(&loc).unwrap().is_obstructed);
+    }
+
+    #[rstest]
+    fn searches_run_against_snapshots(grid: Grid) {
+        let start = Location::new(0, 0, 0);
+        let mut snapshot = grid.snapshot();
+
+        snapshot
+            .toggle_cell_obstruction(&Location::new(1, 0, 0))
+            .unwrap();
+        snapshot
+            .toggle_cell_obstruction(&Location::new(1, 0, 1))
+            .unwrap();
+        snapshot
+            .toggle_cell_obstruction(&Location::new(0, 0, 1))
+            .unwrap();
+
+        assert!(crate::prelude::path_2d(&snapshot, &start, 3)
+            .unwrap()
+            .is_empty());
+        assert!(!crate::prelude::path_2d(&grid, &start, 3)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[rstest]
+    fn entity_index_follows_moves(mut grid: Grid) {
+        let mut world = legion::World::default();
+        let entity = world.push(());
+        let (from, to) = (Location::new(0, 0, 0), Location::new(3, 0, 3));
+
+        grid.add_entity(&from, entity).unwrap();
+        assert_eq!(
+            grid.add_entity(&to, entity),
+            Err(GridError::DuplicateEntity(entity, from))
+        );
+
+        assert_eq!(
+            grid.move_entity(&entity, &Location::new(9, 0, 0)),
+            Err(GridError::OutOfBounds(Location::new(9, 0, 0)))
+        );
+        assert_eq!(grid.entity_location(&entity), Some(from));
+
+        grid.move_entity(&entity, &to).unwrap();
+        assert_eq!(grid.entity_location(&entity), Some(to));
+        assert!(grid.get_ref_cell(&from).unwrap().entities.is_empty());
+        assert_eq!(grid.get_ref_cell(&to).unwrap().entities, vec![entity]);
+
+        assert_eq!(grid.remove_entity(&entity), Ok(to));
+        assert_eq!(grid.entity_location(&entity), None);
+        assert_eq!(
+            grid.remove_entity(&entity),
+            Err(GridError::EntityNotFound(entity))
+        );
+    }
+
+    #[rstest]
+    fn entity_queries(mut grid: Grid) {
+        let mut world = legion::World::default();
+        let locs = [
+            Location::new(1, 0, 1),
+            Location::new(3, 0, 1),
+            Location::new(5, 0, 4),
+            Location::new(1, 1, 1),
+        ];
+        let entities: Vec<Entity> = locs
+            .iter()
+            .map(|loc| {
+                let entity = world.push(());
+                grid.add_entity(loc, entity).unwrap();
+                entity
+            })
+            .collect();
+
+        let near = grid
+            .entities_in_radius(&Location::new(2, 0, 1), 1.0, Metric::Chebyshev)
+            .unwrap();
+        assert_eq!(near, vec![entities[0], entities[1]].into_iter().collect());
+
+        let boxed = grid.entities_in_rect(&Location::new(5, 1, 4), &Location::new(1, 0, 1));
+        assert_eq!(boxed, entities.iter().copied().collect());
+
+        let column = grid.entities_in_rect(&Location::new(1, 0, 0), &Location::new(1, 4, 4));
+        assert_eq!(column, vec![entities[0], entities[3]].into_iter().collect());
+    }
+
     // #[rstest]
     // #[case(&Location(6, 5), false)]
     // #[case(&Location(1, 5), false)]
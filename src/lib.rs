@@ -1,15 +1,52 @@
-#![feature(map_into_keys_values)]
-
+pub mod aoe;
+pub mod ascii;
+pub mod binary;
+pub mod budget;
 pub mod cell;
+pub mod cost;
+pub mod cover;
 pub mod direction;
+pub mod ecs;
+pub mod editor;
+pub mod error;
+pub mod events;
 pub mod grid;
 pub mod location;
+pub mod metric;
+pub mod movement;
+pub mod offset;
 pub mod pathfinding;
+#[cfg(feature = "serde")]
+pub mod serialization;
+pub mod terrain;
+#[cfg(feature = "tiled")]
+pub mod tiled;
+pub mod visibility;
 
 pub mod prelude {
+    pub use crate::aoe::*;
+    pub use crate::binary::*;
+    pub use crate::budget::*;
     pub use crate::cell::*;
+    pub use crate::cost::*;
+    pub use crate::cover::*;
     pub use crate::direction::*;
+    pub use crate::ecs::*;
+    pub use crate::editor::*;
+    pub use crate::error::*;
+    pub use crate::events::*;
     pub use crate::grid::*;
     pub use crate::location::*;
+    pub use crate::metric::*;
+    pub use crate::movement::*;
+    pub use crate::offset::*;
     pub use crate::pathfinding::dijkstra_max_move::*;
+    pub use crate::pathfinding::footprint::*;
+    pub use crate::pathfinding::modes::*;
+    pub use crate::pathfinding::weighted::*;
+    #[cfg(feature = "serde")]
+    pub use crate::serialization::*;
+    pub use crate::terrain::*;
+    #[cfg(feature = "tiled")]
+    pub use crate::tiled::*;
 }
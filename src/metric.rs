@@ -0,0 +1,56 @@
+pub const FEET_PER_SQUARE: f32 = 5.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Metric {
+    Chebyshev,
+    Manhattan,
+    Euclidean,
+    Octile,
+    Alternating,
+}
+
+impl Metric {
+    // Distance in squares for per-axis offsets in any order. Alternating counts
+    // every other diagonal step double, like the 5-10-5 tabletop variant rule.
+    pub fn distance(&self, deltas: &[i32]) -> f32 {
+        let mut deltas: Vec<i32> = deltas.iter().map(|delta| delta.abs()).collect();
+        deltas.sort_unstable_by(|a, b| b.cmp(a));
+
+        let axis = |idx: usize| *deltas.get(idx).unwrap_or(&0);
+        let (largest, middle, smallest) = (axis(0), axis(1), axis(2));
+
+        match *self {
+            Metric::Chebyshev => largest as f32,
+            Metric::Manhattan => deltas.iter().sum::<i32>() as f32,
+            Metric::Euclidean => (deltas.iter().map(|d| d * d).sum::<i32>() as f32).sqrt(),
+            Metric::Octile => {
+                largest as f32
+                    + (2f32.sqrt() - 1.0) * middle as f32
+                    + (3f32.sqrt() - 2f32.sqrt()) * smallest as f32
+            }
+            Metric::Alternating => (largest + middle / 2) as f32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(Metric::Chebyshev, &[3, -4], 4.0)]
+    #[case(Metric::Manhattan, &[3, -4], 7.0)]
+    #[case(Metric::Euclidean, &[3, -4], 5.0)]
+    #[case(Metric::Octile, &[0, 4], 4.0)]
+    #[case(Metric::Octile, &[2, 2], 2.0 * 2f32.sqrt())]
+    #[case(Metric::Alternating, &[3, 3], 4.0)]
+    #[case(Metric::Alternating, &[4, 1], 4.0)]
+    #[case(Metric::Alternating, &[2, 2, 2], 3.0)]
+    #[case(Metric::Chebyshev, &[1, 5, 2], 5.0)]
+    #[case(Metric::Manhattan, &[1, 5, 2], 8.0)]
+    fn distance_tests(#[case] metric: Metric, #[case] deltas: &[i32], #[case] expected: f32) {
+        let result = metric.distance(deltas);
+        assert!((result - expected).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,467 @@
+use crate::prelude::{Boundry, Direction, Grid, GridError, Location};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MovementMode {
+    Walk,
+    Fly,
+    Swim,
+    Climb,
+    Burrow,
+}
+
+static MODES: [MovementMode; 5] = [
+    MovementMode::Walk,
+    MovementMode::Fly,
+    MovementMode::Swim,
+    MovementMode::Climb,
+    MovementMode::Burrow,
+];
+
+impl MovementMode {
+    pub fn all() -> impl Iterator<Item = MovementMode> {
+        MODES.iter().copied()
+    }
+}
+
+// How far a walking creature can jump: up to `distance` cells across and `height`
+// layers up. Jumps cost one movement per cell and layer covered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Jump {
+    pub distance: i8,
+    pub height: i8,
+}
+
+// A speed of 0 means the creature lacks that mode. Movement spent in one mode
+// counts against all of them, so a creature can switch modes as long as what it
+// has spent so far stays within the speed of the mode it switches to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Speeds {
+    pub walk: usize,
+    pub fly: usize,
+    pub swim: usize,
+    pub climb: usize,
+    pub burrow: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub jump: Jump,
+}
+
+impl Speeds {
+    pub fn walking(walk: usize) -> Self {
+        Speeds {
+            walk,
+            ..Speeds::default()
+        }
+    }
+
+    pub fn speed(&self, mode: MovementMode) -> usize {
+        match mode {
+            MovementMode::Walk => self.walk,
+            MovementMode::Fly => self.fly,
+            MovementMode::Swim => self.swim,
+            MovementMode::Climb => self.climb,
+            MovementMode::Burrow => self.burrow,
+        }
+    }
+
+    pub fn modes(&self) -> impl Iterator<Item = MovementMode> + '_ {
+        MovementMode::all().filter(move |mode| self.speed(*mode) > 0)
+    }
+}
+
+impl Grid {
+    // Walking only changes layers on stairs and ramps, which cost what the connector
+    // says, and climbing only changes layers. Beyond that, the terrain of the new
+    // cell decides which modes may enter it and at what cost, and crossing walls
+    // costs as much as it would on foot. Burrowing digs through obstructed cells,
+    // but walls block every mode.
+    pub fn cost_for_mode(
+        &self,
+        current_loc: &Location,
+        new_loc: &Location,
+        mode: MovementMode,
+    ) -> Result<usize, GridError> {
+        let (boundry_cost, terrain_cost) = self.mode_costs(current_loc, new_loc, mode)?;
+
+        Ok(boundry_cost.max(terrain_cost))
+    }
+
+    // What crossing the walls and entering the terrain cost on their own, so
+    // weights can stand in for either.
+    pub(crate) fn mode_costs(
+        &self,
+        current_loc: &Location,
+        new_loc: &Location,
+        mode: MovementMode,
+    ) -> Result<(usize, usize), GridError> {
+        let dir = current_loc.get_dir_from_loc(new_loc)?;
+        let neighbor_cell = self.get_ref_cell(new_loc)?;
+        self.get_ref_cell(current_loc)?;
+
+        let connector = match dir.is_vertical() {
+            true => self.connector_between(current_loc, new_loc)?,
+            false => None,
+        };
+
+        let terrain_cost = match mode {
+            MovementMode::Walk if dir.is_vertical() && connector.is_none() => None,
+            MovementMode::Climb if !dir.is_vertical() => None,
+            _ => self.terrain_table().get(&neighbor_cell.terrain).cost(mode),
+        }
+        .ok_or(GridError::ModeNotAllowed(*current_loc, *new_loc, mode))?;
+
+        if neighbor_cell.is_obstructed && mode != MovementMode::Burrow {
+            return Err(GridError::Obstructed(*new_loc));
+        }
+
+        let burrow = mode == MovementMode::Burrow;
+
+        let boundry_cost = match connector {
+            Some(connector) if mode == MovementMode::Walk => {
+                self.crossing_cost(current_loc, new_loc, burrow)?;
+                connector.cost
+            }
+            _ => self.crossing_cost(current_loc, new_loc, burrow)?,
+        };
+
+        Ok((boundry_cost, terrain_cost))
+    }
+
+    // Walkers need something to stand on: the bottom of the grid, an obstructed cell
+    // or a boundry between them and the cell below, or stairs or a ramp leading down.
+    pub fn is_standable(&self, loc: &Location) -> bool {
+        let cell = match self.get_ref_cell(loc) {
+            Ok(cell) => cell,
+            Err(_) => return false,
+        };
+
+        let below = loc.get_loc_from_dir(&Direction::D);
+
+        loc.y == 0
+            || self
+                .get_ref_cell(&below)
+                .is_ok_and(|cell| cell.is_obstructed)
+            || self
+                .boundry_between(loc, &below)
+                .is_ok_and(|boundry| boundry.is_some())
+            || cell
+                .connectors
+                .iter()
+                .flatten()
+                .any(|(dir, _)| dir.offset().y < 0)
+    }
+
+    // Where something stepping into `loc` comes to rest, and how many layers it fell
+    // to get there.
+    pub fn landing(&self, loc: &Location) -> Result<(Location, i8), GridError> {
+        self.get_ref_cell(loc)?;
+        let mut landing = *loc;
+
+        while !self.is_standable(&landing) {
+            landing = landing.get_loc_from_dir(&Direction::D);
+        }
+
+        Ok((landing, loc.y - landing.y))
+    }
+
+    // Steps that change layers and move across at once pass the cell beside them on
+    // their own layer and the one above or below them, the way 2D diagonals pass
+    // the cells at their corner. Like those, they cost as much as the most expensive
+    // face they could cross, and any Full wall, ceiling or obstruction in the way
+    // blocks them.
+    fn crossing_cost(
+        &self,
+        current_loc: &Location,
+        new_loc: &Location,
+        burrow: bool,
+    ) -> Result<usize, GridError> {
+        let offset = current_loc.get_dir_from_loc(new_loc)?.offset();
+
+        if offset.y == 0 {
+            return match burrow {
+                true => self.wall_cost_2d(current_loc, new_loc),
+                false => self.boundry_cost_2d(current_loc, new_loc),
+            };
+        }
+
+        let cost = self.edge_cost(current_loc, new_loc)?;
+
+        if offset.x == 0 && offset.z == 0 {
+            return Ok(cost);
+        }
+
+        let beside = Location::new(new_loc.x, current_loc.y, new_loc.z);
+        let over = Location::new(current_loc.x, new_loc.y, current_loc.z);
+
+        if !burrow && self.get_ref_cell(&over)?.is_obstructed {
+            return Err(GridError::Obstructed(over));
+        }
+
+        let faces = [
+            self.edge_cost(current_loc, &over),
+            self.edge_cost(&beside, new_loc),
+            self.crossing_cost(current_loc, &beside, burrow),
+            self.crossing_cost(&over, new_loc, burrow),
+        ];
+
+        faces.iter().try_fold(cost, |cost, face| match face {
+            Ok(face) => Ok(cost.max(*face)),
+            Err(GridError::BlockedByWall(..)) => {
+                Err(GridError::BlockedByWall(*current_loc, *new_loc))
+            }
+            Err(err) => Err(*err),
+        })
+    }
+
+    fn edge_cost(&self, current_loc: &Location, new_loc: &Location) -> Result<usize, GridError> {
+        match self.boundry_between(current_loc, new_loc)? {
+            Some(Boundry::Full) => Err(GridError::BlockedByWall(*current_loc, *new_loc)),
+            Some(Boundry::Half) => Ok(2),
+            None => Ok(1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::{Cell, Connector, Terrain, TerrainRules};
+    use rstest::{fixture, rstest};
+
+    #[fixture]
+    fn grid() -> Grid {
+        Grid::from_ascii(
+            "\
++ + + + +
+ . ~ % #
++ + + + +
+
++ + + + +
+ . . . .
++ + + + +
+",
+        )
+        .unwrap()
+    }
+
+    #[rstest]
+    #[case(MovementMode::Walk, Location::new(1, 0, 0), Ok(2))]
+    #[case(MovementMode::Fly, Location::new(1, 0, 0), Ok(1))]
+    #[case(MovementMode::Burrow, Location::new(1, 0, 0), Ok(1))]
+    #[case(MovementMode::Climb, Location::new(1, 0, 0), Err(MovementMode::Climb))]
+    #[case(MovementMode::Walk, Location::new(0, 1, 0), Err(MovementMode::Walk))]
+    #[case(MovementMode::Climb, Location::new(0, 1, 0), Ok(1))]
+    #[case(MovementMode::Fly, Location::new(1, 1, 0), Ok(1))]
+    #[case(MovementMode::Swim, Location::new(1, 0, 0), Err(MovementMode::Swim))]
+    fn mode_costs(
+        grid: Grid,
+        #[case] mode: MovementMode,
+        #[case] to: Location,
+        #[case] expected: Result<usize, MovementMode>,
+    ) {
+        let from = Location::new(0, 0, 0);
+
+        assert_eq!(
+            grid.cost_for_mode(&from, &to, mode),
+            expected.map_err(|mode| GridError::ModeNotAllowed(from, to, mode))
+        );
+    }
+
+    #[rstest]
+    #[case(MovementMode::Fly, Direction::U)]
+    #[case(MovementMode::Climb, Direction::U)]
+    #[case(MovementMode::Burrow, Direction::U)]
+    #[case(MovementMode::Fly, Direction::E)]
+    #[case(MovementMode::Climb, Direction::E)]
+    #[case(MovementMode::Burrow, Direction::E)]
+    fn rising_diagonals_respect_walls_and_ceilings(
+        mut grid: Grid,
+        #[case] mode: MovementMode,
+        #[case] wall: Direction,
+    ) {
+        let (from, to) = (Location::new(0, 0, 0), Location::new(1, 1, 0));
+
+        assert_eq!(grid.cost_for_mode(&from, &to, mode), Ok(1));
+
+        grid.add_cell_boundry_and_adjacent(&from, wall, Boundry::Full)
+            .unwrap();
+
+        assert_eq!(
+            grid.cost_for_mode(&from, &to, mode),
+            Err(GridError::BlockedByWall(from, to))
+        );
+    }
+
+    #[rstest]
+    fn only_swimmers_enter_water(grid: Grid) {
+        let (from, to) = (Location::new(1, 0, 0), Location::new(2, 0, 0));
+
+        assert_eq!(grid.cost_for_mode(&from, &to, MovementMode::Swim), Ok(1));
+        assert_eq!(
+            grid.cost_for_mode(&from, &to, MovementMode::Walk),
+            Err(GridError::ModeNotAllowed(from, to, MovementMode::Walk))
+        );
+        assert_eq!(grid.cost_for_mode(&from, &to, MovementMode::Fly), Ok(1));
+    }
+
+    #[rstest]
+    fn only_burrowers_dig(grid: Grid) {
+        let (from, to) = (Location::new(2, 0, 0), Location::new(3, 0, 0));
+
+        assert_eq!(
+            grid.cost_for_mode(&from, &to, MovementMode::Fly),
+            Err(GridError::Obstructed(to))
+        );
+        assert_eq!(grid.cost_for_mode(&from, &to, MovementMode::Burrow), Ok(1));
+    }
+
+    #[rstest]
+    fn costs_come_from_the_terrain_table(mut grid: Grid) {
+        let (from, to) = (Location::new(0, 0, 0), Location::new(1, 0, 0));
+        let mud = Terrain::Custom(1);
+
+        grid.set_cell_terrain(&to, mud).unwrap();
+        assert_eq!(grid.cost_2d(&from, &to), Ok(1));
+
+        grid.define_terrain(
+            mud,
+            TerrainRules {
+                costs: vec![(MovementMode::Walk, 3)].into_iter().collect(),
+                ..TerrainRules::default()
+            },
+        );
+
+        assert_eq!(grid.cost_2d(&from, &to), Ok(3));
+        assert_eq!(
+            grid.cost_for_mode(&from, &to, MovementMode::Fly),
+            Err(GridError::ModeNotAllowed(from, to, MovementMode::Fly))
+        );
+    }
+
+    #[rstest]
+    fn walkers_take_stairs_and_ramps(mut grid: Grid) {
+        let (from, up, across) = (
+            Location::new(0, 0, 0),
+            Location::new(0, 1, 0),
+            Location::new(1, 1, 0),
+        );
+
+        grid.add_cell_connector_and_adjacent(&from, Direction::U, Connector::stairs(3))
+            .unwrap();
+        grid.add_cell_connector_and_adjacent(&from, Direction::UE, Connector::ramp(1))
+            .unwrap();
+
+        assert_eq!(grid.cost_for_mode(&from, &up, MovementMode::Walk), Ok(3));
+        assert_eq!(grid.cost_for_mode(&up, &from, MovementMode::Walk), Ok(3));
+        assert_eq!(grid.cost_for_mode(&from, &up, MovementMode::Climb), Ok(1));
+        assert_eq!(
+            grid.cost_for_mode(&from, &across, MovementMode::Walk),
+            Ok(1)
+        );
+
+        grid.add_cell_boundry_and_adjacent(&from, Direction::U, Boundry::Full)
+            .unwrap();
+        assert_eq!(
+            grid.cost_for_mode(&from, &up, MovementMode::Walk),
+            Err(GridError::BlockedByWall(from, up))
+        );
+
+        grid.remove_cell_connector_and_adjacent(&across, Direction::DW)
+            .unwrap();
+        assert_eq!(
+            grid.cost_for_mode(&from, &across, MovementMode::Walk),
+            Err(GridError::ModeNotAllowed(from, across, MovementMode::Walk))
+        );
+    }
+
+    #[rstest]
+    #[case(Location::new(0, 0, 0), true)]
+    #[case(Location::new(0, 1, 0), false)]
+    #[case(Location::new(3, 1, 0), true)]
+    fn support(grid: Grid, #[case] loc: Location, #[case] expected: bool) {
+        assert_eq!(grid.is_standable(&loc), expected);
+    }
+
+    #[rstest]
+    fn walkers_stand_on_floors_and_stairs(mut grid: Grid) {
+        let (floor, stairs) = (Location::new(1, 1, 0), Location::new(2, 1, 0));
+
+        grid.add_cell_boundry_and_adjacent(&floor, Direction::D, Boundry::Half)
+            .unwrap();
+        grid.add_cell_connector_and_adjacent(
+            &Location::new(1, 0, 0),
+            Direction::UE,
+            Connector::ramp(1),
+        )
+        .unwrap();
+
+        assert!(grid.is_standable(&floor) && grid.is_standable(&stairs));
+        assert!(!grid.is_standable(&Location::new(1, 2, 0)));
+        assert_eq!(grid.landing(&floor), Ok((floor, 0)));
+        assert_eq!(
+            grid.landing(&Location::new(0, 1, 0)),
+            Ok((Location::new(0, 0, 0), 1))
+        );
+    }
+
+    #[rstest]
+    fn ramps_pass_under_ceilings(mut grid: Grid) {
+        let (from, to) = (Location::new(0, 0, 0), Location::new(1, 1, 0));
+
+        grid.add_cell_connector_and_adjacent(&from, Direction::UE, Connector::ramp(1))
+            .unwrap();
+        grid.add_cell_boundry_and_adjacent(&Location::new(1, 0, 0), Direction::U, Boundry::Full)
+            .unwrap();
+
+        assert_eq!(
+            grid.cost_for_mode(&from, &to, MovementMode::Walk),
+            Err(GridError::BlockedByWall(from, to))
+        );
+    }
+
+    #[rstest]
+    fn connectors_change_layers(mut grid: Grid) {
+        let loc = Location::new(0, 0, 0);
+
+        assert_eq!(
+            grid.add_cell_connector_and_adjacent(&loc, Direction::E, Connector::stairs(1)),
+            Err(GridError::NotVertical(loc, Direction::E))
+        );
+        assert_eq!(
+            grid.add_cell_connector_and_adjacent(&loc, Direction::D, Connector::stairs(1)),
+            Err(GridError::OutOfBounds(Location::new(0, -1, 0)))
+        );
+        assert_eq!(grid.get_ref_cell(&loc).unwrap().connectors, None);
+
+        let mut cell = Cell::new_empty(loc);
+        assert_eq!(
+            cell.add_connector(Direction::E, Connector::stairs(1)),
+            Err(GridError::NotVertical(loc, Direction::E))
+        );
+
+        cell.connectors = Some(
+            vec![(Direction::E, Connector::stairs(1))]
+                .into_iter()
+                .collect(),
+        );
+        assert_eq!(
+            Grid::from_cells(1, 1, 1, vec![cell]),
+            Err(GridError::NotVertical(loc, Direction::E))
+        );
+    }
+
+    #[test]
+    fn speeds_list_their_modes() {
+        let speeds = Speeds {
+            fly: 6,
+            ..Speeds::walking(4)
+        };
+
+        assert_eq!(
+            speeds.modes().collect::<Vec<_>>(),
+            vec![MovementMode::Walk, MovementMode::Fly]
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use crate::location::Location;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+#[derive(PartialEq, Clone, Copy, Eq, Hash, Ord, PartialOrd, Debug, Default)]
+pub struct Offset {
+    pub x: i8,
+    pub y: i8,
+    pub z: i8,
+}
+
+impl Offset {
+    pub fn new(x: i8, y: i8, z: i8) -> Self {
+        Offset { x, y, z }
+    }
+}
+
+// Every operator clamps coordinates past the ends of `i8` to them instead of
+// wrapping around.
+impl Add for Offset {
+    type Output = Offset;
+
+    fn add(self, other: Offset) -> Offset {
+        Offset::new(
+            self.x.saturating_add(other.x),
+            self.y.saturating_add(other.y),
+            self.z.saturating_add(other.z),
+        )
+    }
+}
+
+impl Sub for Offset {
+    type Output = Offset;
+
+    fn sub(self, other: Offset) -> Offset {
+        Offset::new(
+            self.x.saturating_sub(other.x),
+            self.y.saturating_sub(other.y),
+            self.z.saturating_sub(other.z),
+        )
+    }
+}
+
+impl Neg for Offset {
+    type Output = Offset;
+
+    fn neg(self) -> Offset {
+        Offset::new(
+            self.x.saturating_neg(),
+            self.y.saturating_neg(),
+            self.z.saturating_neg(),
+        )
+    }
+}
+
+impl Mul<i8> for Offset {
+    type Output = Offset;
+
+    fn mul(self, scale: i8) -> Offset {
+        Offset::new(
+            self.x.saturating_mul(scale),
+            self.y.saturating_mul(scale),
+            self.z.saturating_mul(scale),
+        )
+    }
+}
+
+impl Add<Offset> for Location {
+    type Output = Location;
+
+    fn add(self, offset: Offset) -> Location {
+        Location::new(
+            self.x.saturating_add(offset.x),
+            self.y.saturating_add(offset.y),
+            self.z.saturating_add(offset.z),
+        )
+    }
+}
+
+impl AddAssign<Offset> for Location {
+    fn add_assign(&mut self, offset: Offset) {
+        *self = *self + offset;
+    }
+}
+
+impl Sub<Offset> for Location {
+    type Output = Location;
+
+    fn sub(self, offset: Offset) -> Location {
+        Location::new(
+            self.x.saturating_sub(offset.x),
+            self.y.saturating_sub(offset.y),
+            self.z.saturating_sub(offset.z),
+        )
+    }
+}
+
+impl SubAssign<Offset> for Location {
+    fn sub_assign(&mut self, offset: Offset) {
+        *self = *self - offset;
+    }
+}
+
+impl Sub for Location {
+    type Output = Offset;
+
+    fn sub(self, other: Location) -> Offset {
+        Offset::new(
+            self.x.saturating_sub(other.x),
+            self.y.saturating_sub(other.y),
+            self.z.saturating_sub(other.z),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn location_offset_arithmetic() {
+        let loc = Location::new(2, 1, 3);
+        let offset = Offset::new(1, -1, 2);
+
+        assert_eq!(loc + offset, Location::new(3, 0, 5));
+        assert_eq!(loc - offset, Location::new(1, 2, 1));
+        assert_eq!((loc + offset) - loc, offset);
+        assert_eq!(loc + offset * 2 + -offset, loc + offset);
+    }
+
+    #[test]
+    fn arithmetic_clamps_to_i8() {
+        let (near, far) = (Location::new(-100, 0, 100), Location::new(100, 0, -100));
+
+        assert_eq!(far - near, Offset::new(127, 0, -128));
+        assert_eq!(far + Offset::new(100, 0, -100), Location::new(127, 0, -128));
+        assert_eq!(
+            near - Offset::new(100, 0, -100),
+            Location::new(-128, 0, 127)
+        );
+        assert_eq!(
+            Offset::new(100, 0, -100) + Offset::new(100, 0, -100),
+            Offset::new(127, 0, -128)
+        );
+        assert_eq!(-Offset::new(-128, 0, 127), Offset::new(127, 0, -127));
+        assert_eq!(Offset::new(100, 0, -100) * 2, Offset::new(127, 0, -128));
+    }
+}
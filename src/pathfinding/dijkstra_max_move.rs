@@ -1,9 +1,15 @@
-use crate::prelude::{Grid, Location};
+use crate::prelude::{Grid, GridError, Location, MovementMode};
 use std::collections::{BinaryHeap, HashMap, HashSet};
 
 use super::BHeapState;
 
-pub fn path_2d(grid: Grid, start: &Location, max_move: usize) -> HashSet<Location> {
+pub fn path_2d(
+    grid: &Grid,
+    start: &Location,
+    max_move: usize,
+) -> Result<HashSet<Location>, GridError> {
+    grid.get_ref_cell(start)?;
+
     let mut visited = HashSet::new();
     let mut cost_so_far = HashMap::new();
 
@@ -13,8 +19,21 @@ pub fn path_2d(grid: Grid, start: &Location, max_move: usize) -> HashSet<Locatio
     cost_so_far.insert(*start, 0);
 
     while let Some(BHeapState(_, item)) = heap.pop() {
+        if item != *start && grid.stops_movement(&item, MovementMode::Walk) {
+            continue;
+        }
+
         for next in grid.visitable_neighbors_2d_iter(item) {
-            let new_cost = *cost_so_far.get(&item).unwrap() + grid.cost_2d(&item, &next);
+            if !grid.is_standable(&next) {
+                continue;
+            }
+
+            let new_cost = *cost_so_far.get(&item).unwrap()
+                + match grid.cost_2d(&item, &next) {
+                    Ok(cost) => cost,
+                    Err(GridError::ModeNotAllowed(..)) => continue,
+                    Err(err) => return Err(err),
+                };
 
             if max_move >= new_cost
                 && (!cost_so_far.contains_key(&next) || new_cost < *cost_so_far.get(&next).unwrap())
@@ -26,42 +45,83 @@ pub fn path_2d(grid: Grid, start: &Location, max_move: usize) -> HashSet<Locatio
         }
     }
 
-    visited
+    Ok(visited)
 }
 
-// #[cfg(test)]
-// mod tests {
-//     use super::*;
-//     use rstest::rstest;
-//     use std::collections::HashSet;
-
-//     fn initial_data() -> (Grid, usize) {
-//         let mut walls = HashSet::new();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::Terrain;
+    use rstest::{fixture, rstest};
+
+    #[fixture]
+    fn grid() -> Grid {
+        Grid::from_ascii(
+            "\
++ + + + + + +
+ . . . . . .
++ + + + + + +
+ . # # # . .
++ + + + + + +
+ . # . . . .
++ + + + + + +
+ . . ~ . . .
++ + + + + + +
+ . . ~ ~ . .
++ + + + + + +
+",
+        )
+        .unwrap()
+    }
 
-//         walls.insert(Location(1, 1));
-//         walls.insert(Location(2, 1));
-//         walls.insert(Location(3, 1));
-//         walls.insert(Location(1, 2));
+    #[rstest]
+    #[case(&Location::new(3, 0, 3), vec![Location::new(4, 0, 1), Location::new(5, 0, 1), Location::new(2, 0, 2), Location::new(3, 0, 2), Location::new(4, 0, 2), Location::new(5, 0, 2),
+    Location::new(2, 0, 3), Location::new(4, 0, 3), Location::new(5, 0, 3), Location::new(2, 0, 4), Location::new(3, 0, 4), Location::new(4, 0, 4), Location::new(5, 0, 4)])]
+    fn is_locations(grid: Grid, #[case] start: &Location, #[case] expected: Vec<Location>) {
+        let result = path_2d(&grid, start, 2).unwrap();
 
-//         let mut difficult_terrain = HashSet::new();
+        assert_eq!(result, expected.into_iter().collect::<HashSet<Location>>());
+    }
 
-//         difficult_terrain.insert(Location(2, 3));
-//         difficult_terrain.insert(Location(2, 4));
-//         difficult_terrain.insert(Location(3, 4));
+    #[rstest]
+    #[case(Terrain::Lava, true, false)]
+    #[case(Terrain::Water, false, false)]
+    #[case(Terrain::Floor, true, true)]
+    fn terrain_limits_movement(
+        mut grid: Grid,
+        #[case] terrain: Terrain,
+        #[case] entered: bool,
+        #[case] crossed: bool,
+    ) {
+        let (start, crossing) = (Location::new(0, 0, 4), Location::new(0, 0, 3));
+
+        for loc in [crossing, Location::new(1, 0, 3), Location::new(1, 0, 4)].iter() {
+            grid.set_cell_terrain(loc, terrain).unwrap();
+        }
 
-//         let grid = Grid::new(6, 5, Some(walls), Some(difficult_terrain), None);
+        let result = path_2d(&grid, &start, 4).unwrap();
 
-//         (grid, 2)
-//     }
+        assert_eq!(result.contains(&crossing), entered);
+        assert_eq!(result.contains(&Location::new(0, 0, 2)), crossed);
+    }
 
-//     #[rstest]
-//     #[case(&Location(3, 3), vec![Location(4, 1), Location(5, 1), Location(2, 2), Location(3, 2), Location(4, 2), Location(5, 2), Location(1, 3), Location(2, 3), Location(4, 3), Location(5, 3),
-//     Location(2, 4), Location(3, 4), Location(4, 4), Location(5, 4)].into_iter().collect::<HashSet<Location>>())]
-//     fn is_locations(#[case] start: &Location, #[case] expected: HashSet<Location>) {
-//         let (grid, max_move) = initial_data();
+    #[test]
+    fn walkers_stay_off_ledges() {
+        let grid =
+            Grid::from_ascii("+ + + +\n # # .\n+ + + +\n\n+ + + +\n . . .\n+ + + +").unwrap();
 
-//         let result = path(grid, start, max_move);
+        assert_eq!(
+            path_2d(&grid, &Location::new(0, 1, 0), 3).unwrap(),
+            vec![Location::new(1, 1, 0)].into_iter().collect()
+        );
+    }
 
-//         assert_eq!(result, expected);
-//     }
-// }
+    #[rstest]
+    fn start_out_of_bounds(grid: Grid) {
+        let start = Location::new(6, 0, 0);
+        assert_eq!(
+            path_2d(&grid, &start, 2),
+            Err(GridError::OutOfBounds(start))
+        );
+    }
+}
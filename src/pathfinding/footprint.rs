@@ -0,0 +1,641 @@
+use crate::prelude::{Boundry, Direction, Grid, GridError, Location, MovementMode};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use super::BHeapState;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CreatureSize {
+    Tiny,
+    Small,
+    Medium,
+    Large,
+    Huge,
+    Gargantuan,
+}
+
+impl CreatureSize {
+    pub fn squares(&self) -> i8 {
+        match self {
+            CreatureSize::Tiny | CreatureSize::Small | CreatureSize::Medium => 1,
+            CreatureSize::Large => 2,
+            CreatureSize::Huge => 3,
+            CreatureSize::Gargantuan => 4,
+        }
+    }
+}
+
+// The cells a creature covers, anchored at its lowest x, y and z corner. Every
+// footprint covers at least one cell.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Footprint {
+    size: i8,
+    height: i8,
+}
+
+impl Footprint {
+    pub fn square(size: i8) -> Option<Self> {
+        Some(Footprint { size, height: 1 }).filter(|_| size >= 1)
+    }
+
+    pub fn cube(size: i8) -> Option<Self> {
+        Some(Footprint { size, height: size }).filter(|_| size >= 1)
+    }
+
+    pub fn size(&self) -> i8 {
+        self.size
+    }
+
+    pub fn height(&self) -> i8 {
+        self.height
+    }
+
+    // Cells past the end of the coordinates are left out, as no grid has them.
+    pub fn cells(&self, anchor: &Location) -> impl Iterator<Item = Location> {
+        let (anchor, size, height) = (*anchor, self.size, self.height);
+
+        (0..height).flat_map(move |dy| {
+            (0..size).flat_map(move |dz| {
+                (0..size).filter_map(move |dx| {
+                    Some(Location::new(
+                        anchor.x.checked_add(dx)?,
+                        anchor.y.checked_add(dy)?,
+                        anchor.z.checked_add(dz)?,
+                    ))
+                })
+            })
+        })
+    }
+
+    pub fn covers(&self, anchor: &Location, loc: &Location) -> bool {
+        let within = |start: i8, len: i8, at: i8| {
+            (start as i32..start as i32 + len as i32).contains(&(at as i32))
+        };
+
+        within(anchor.x, self.size, loc.x)
+            && within(anchor.y, self.height, loc.y)
+            && within(anchor.z, self.size, loc.z)
+    }
+
+    // Squeezing creatures take up the footprint one size smaller at the same anchor.
+    pub fn squeezed(&self) -> Option<Footprint> {
+        match self.size {
+            size if size > 1 => Some(Footprint {
+                size: size - 1,
+                height: (self.height - 1).max(1),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl From<CreatureSize> for Footprint {
+    fn from(size: CreatureSize) -> Self {
+        Footprint {
+            size: size.squares(),
+            height: 1,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FootprintStep {
+    pub anchor: Location,
+    pub squeezed: bool,
+}
+
+// Anchors a creature can reach by sliding its footprint across its layer. With
+// `squeeze`, it may also pass through spaces that only fit its squeezed footprint,
+// at double cost.
+pub fn footprint_range_2d(
+    grid: &Grid,
+    start: &Location,
+    footprint: &Footprint,
+    max_move: usize,
+    squeeze: bool,
+) -> Result<HashSet<Location>, GridError> {
+    Ok(
+        search(grid, start, None, footprint, squeeze, false, max_move)?
+            .into_keys()
+            .filter(|anchor| anchor != start)
+            .collect(),
+    )
+}
+
+// Like `footprint_range_2d`, but the footprint may also move between layers where
+// every covered cell has stairs or a ramp to take.
+pub fn footprint_range_3d(
+    grid: &Grid,
+    start: &Location,
+    footprint: &Footprint,
+    max_move: usize,
+    squeeze: bool,
+) -> Result<HashSet<Location>, GridError> {
+    Ok(
+        search(grid, start, None, footprint, squeeze, true, max_move)?
+            .into_keys()
+            .filter(|anchor| anchor != start)
+            .collect(),
+    )
+}
+
+// The cheapest chain of steps from `start` to `goal`, both included, with its cost.
+pub fn footprint_path_2d(
+    grid: &Grid,
+    start: &Location,
+    goal: &Location,
+    footprint: &Footprint,
+    squeeze: bool,
+) -> Result<Option<(Vec<FootprintStep>, usize)>, GridError> {
+    path(grid, start, goal, footprint, squeeze, false)
+}
+
+pub fn footprint_path_3d(
+    grid: &Grid,
+    start: &Location,
+    goal: &Location,
+    footprint: &Footprint,
+    squeeze: bool,
+) -> Result<Option<(Vec<FootprintStep>, usize)>, GridError> {
+    path(grid, start, goal, footprint, squeeze, true)
+}
+
+fn path(
+    grid: &Grid,
+    start: &Location,
+    goal: &Location,
+    footprint: &Footprint,
+    squeeze: bool,
+    is_3d: bool,
+) -> Result<Option<(Vec<FootprintStep>, usize)>, GridError> {
+    let visited = search(
+        grid,
+        start,
+        Some(goal),
+        footprint,
+        squeeze,
+        is_3d,
+        usize::MAX,
+    )?;
+
+    let cost = match visited.get(goal) {
+        Some(visit) => visit.cost,
+        None => return Ok(None),
+    };
+
+    let mut steps = vec![];
+    let mut anchor = Some(*goal);
+
+    while let Some(visit) = anchor.and_then(|anchor| visited.get(&anchor)) {
+        steps.push(FootprintStep {
+            anchor: anchor.unwrap(),
+            squeezed: visit.squeezed,
+        });
+        anchor = visit.previous;
+    }
+
+    steps.reverse();
+
+    Ok(Some((steps, cost)))
+}
+
+struct Visit {
+    cost: usize,
+    previous: Option<Location>,
+    squeezed: bool,
+}
+
+// Dijkstra over anchors, returning how each reached anchor was reached. Stops early
+// once `goal` is settled.
+fn search(
+    grid: &Grid,
+    start: &Location,
+    goal: Option<&Location>,
+    footprint: &Footprint,
+    squeeze: bool,
+    is_3d: bool,
+    max_move: usize,
+) -> Result<HashMap<Location, Visit>, GridError> {
+    grid.get_ref_cell(start)?;
+
+    let mut visited = HashMap::new();
+
+    let squeezed = match occupied(grid, footprint, start, squeeze) {
+        Some((_, squeezed)) => squeezed,
+        None => return Ok(visited),
+    };
+
+    let mut heap = BinaryHeap::new();
+
+    heap.push(Reverse(BHeapState::new(0, *start)));
+    visited.insert(
+        *start,
+        Visit {
+            cost: 0,
+            previous: None,
+            squeezed,
+        },
+    );
+
+    while let Some(Reverse(BHeapState(cost, anchor))) = heap.pop() {
+        if cost > visited[&anchor].cost {
+            continue;
+        }
+
+        if goal == Some(&anchor) {
+            break;
+        }
+
+        let covered = match visited[&anchor].squeezed {
+            true => footprint.squeezed().unwrap(),
+            false => *footprint,
+        };
+
+        if anchor != *start
+            && covered
+                .cells(&anchor)
+                .any(|loc| grid.stops_movement(&loc, MovementMode::Walk))
+        {
+            continue;
+        }
+
+        let dirs: Box<dyn Iterator<Item = Direction>> = match is_3d {
+            true => Box::new(Direction::all_3d()),
+            false => Box::new(Direction::all_2d()),
+        };
+
+        for dir in dirs {
+            let next = anchor.get_loc_from_dir(&dir);
+
+            let (step_cost, squeezed) = match step_cost(grid, footprint, &anchor, &dir, squeeze)? {
+                Some(step) => step,
+                None => continue,
+            };
+
+            let new_cost = cost + step_cost;
+
+            if new_cost <= max_move && visited.get(&next).is_none_or(|visit| new_cost < visit.cost)
+            {
+                visited.insert(
+                    next,
+                    Visit {
+                        cost: new_cost,
+                        previous: Some(anchor),
+                        squeezed,
+                    },
+                );
+                heap.push(Reverse(BHeapState::new(new_cost, next)));
+            }
+        }
+    }
+
+    Ok(visited)
+}
+
+// Every covered cell must be open, with no Full wall between two of them.
+fn fits(grid: &Grid, footprint: &Footprint, anchor: &Location) -> bool {
+    let on_grid = |start: i8, len: i8, bound: i8| start as i32 + len as i32 <= bound as i32;
+
+    on_grid(anchor.x, footprint.size, grid.length)
+        && on_grid(anchor.y, footprint.height, grid.height)
+        && on_grid(anchor.z, footprint.size, grid.width)
+        && footprint
+            .cells(anchor)
+            .all(|loc| match grid.get_ref_cell(&loc) {
+                Ok(cell) => {
+                    !cell.is_obstructed
+                        && cell.boundries.iter().flatten().all(|(dir, boundry)| {
+                            *boundry != Boundry::Full
+                                || !footprint.covers(anchor, &loc.get_loc_from_dir(dir))
+                        })
+                }
+                Err(_) => false,
+            })
+}
+
+// The footprint a creature takes up at `anchor`, and whether it had to squeeze.
+fn occupied(
+    grid: &Grid,
+    footprint: &Footprint,
+    anchor: &Location,
+    squeeze: bool,
+) -> Option<(Footprint, bool)> {
+    if fits(grid, footprint, anchor) {
+        return Some((*footprint, false));
+    }
+
+    footprint
+        .squeezed()
+        .filter(|squeezed| squeeze && fits(grid, squeezed, anchor))
+        .map(|squeezed| (squeezed, true))
+}
+
+// Every covered cell takes the same step, and the move costs as much as the most
+// expensive of those steps, doubled when squeezing into the new anchor. The
+// bottom layer of the footprint must end up standing on something. A creature
+// that is squeezed at either end moves in its squeezed footprint. `None` when the
+// creature does not fit or any step is blocked.
+fn step_cost(
+    grid: &Grid,
+    footprint: &Footprint,
+    anchor: &Location,
+    dir: &Direction,
+    squeeze: bool,
+) -> Result<Option<(usize, bool)>, GridError> {
+    let (moving, squeezed) = match occupied(grid, footprint, &anchor.get_loc_from_dir(dir), squeeze)
+    {
+        Some((_, true)) => (footprint.squeezed().unwrap(), true),
+        Some(_) if !fits(grid, footprint, anchor) => (footprint.squeezed().unwrap(), false),
+        Some(_) => (*footprint, false),
+        None => return Ok(None),
+    };
+
+    let mut cost = 1;
+
+    for loc in moving.cells(anchor) {
+        let next = loc.get_loc_from_dir(dir);
+
+        if loc.y == anchor.y && !grid.is_standable(&next) {
+            return Ok(None);
+        }
+
+        match grid.cost_for_mode(&loc, &next, MovementMode::Walk) {
+            Ok(step) => cost = cost.max(step),
+            Err(GridError::BlockedByWall(..))
+            | Err(GridError::Obstructed(_))
+            | Err(GridError::ModeNotAllowed(..)) => return Ok(None),
+            Err(err) => return Err(err),
+        }
+    }
+
+    match squeezed {
+        true => Ok(Some((cost * 2, true))),
+        false => Ok(Some((cost, false))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::Connector;
+    use rstest::{fixture, rstest};
+
+    #[fixture]
+    fn grid() -> Grid {
+        Grid::from_ascii(
+            "\
++ + + + + + +
+ . . . . . .
++ + + + + + +
+ . . . # . .
++ + + + + + +
+ . . . . . .
++ + + + + + +
+ . . . . . .
++ + + + + + +
+ . . .|. ~ .
++ + + + + + +
+",
+        )
+        .unwrap()
+    }
+
+    #[rstest]
+    fn medium_creatures_match_path_2d(grid: Grid) {
+        let start = Location::new(2, 0, 2);
+
+        assert_eq!(
+            footprint_range_2d(&grid, &start, &CreatureSize::Medium.into(), 2, false).unwrap(),
+            crate::prelude::path_2d(&grid, &start, 2).unwrap()
+        );
+    }
+
+    #[rstest]
+    #[case(Location::new(0, 0, 0), true)]
+    #[case(Location::new(2, 0, 0), false)]
+    #[case(Location::new(3, 0, 1), false)]
+    #[case(Location::new(1, 0, 3), true)]
+    #[case(Location::new(2, 0, 3), false)]
+    #[case(Location::new(5, 0, 0), false)]
+    fn large_creatures_need_room(grid: Grid, #[case] anchor: Location, #[case] expected: bool) {
+        assert_eq!(fits(&grid, &CreatureSize::Large.into(), &anchor), expected);
+    }
+
+    #[rstest]
+    #[case(Footprint::square(0), None)]
+    #[case(Footprint::cube(-1), None)]
+    #[case(Footprint::square(2), Some((2, 1)))]
+    #[case(Footprint::cube(2), Some((2, 2)))]
+    fn footprints_cover_at_least_one_cell(
+        #[case] footprint: Option<Footprint>,
+        #[case] expected: Option<(i8, i8)>,
+    ) {
+        assert_eq!(
+            footprint.map(|footprint| (footprint.size(), footprint.height())),
+            expected
+        );
+    }
+
+    #[test]
+    fn footprints_stop_at_the_last_coordinate() {
+        let mut grid = Grid::new(127, 5, 1);
+        grid.add_cell_boundry_and_adjacent(&Location::new(126, 0, 1), Direction::E, Boundry::Full)
+            .unwrap();
+
+        let range = footprint_range_2d(
+            &grid,
+            &Location::new(123, 0, 0),
+            &Footprint::square(3).unwrap(),
+            2,
+            false,
+        )
+        .unwrap();
+
+        assert!(range.contains(&Location::new(124, 0, 0)));
+        assert!(!range.contains(&Location::new(125, 0, 0)));
+    }
+
+    #[rstest]
+    fn large_creatures_go_around_obstructions(grid: Grid) {
+        let footprint = CreatureSize::Large.into();
+        let (start, goal) = (Location::new(0, 0, 0), Location::new(4, 0, 0));
+
+        let (steps, cost) = footprint_path_2d(&grid, &start, &goal, &footprint, false)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(steps.first().map(|step| step.anchor), Some(start));
+        assert_eq!(steps.last().map(|step| step.anchor), Some(goal));
+        assert!(steps
+            .iter()
+            .all(|step| !footprint.covers(&step.anchor, &Location::new(3, 0, 1))));
+        assert_eq!(cost, steps.len() - 1);
+
+        let range = footprint_range_2d(&grid, &start, &footprint, 2, false).unwrap();
+        assert!(range.contains(&Location::new(1, 0, 2)));
+        assert!(!range.contains(&Location::new(2, 0, 0)));
+
+        assert_eq!(
+            footprint_path_2d(&grid, &start, &Location::new(3, 0, 0), &footprint, false),
+            Ok(None)
+        );
+    }
+
+    #[rstest]
+    fn difficult_terrain_under_any_cell_costs_double(grid: Grid) {
+        let footprint = Footprint::square(2).unwrap();
+
+        let (_, cost) = footprint_path_2d(
+            &grid,
+            &Location::new(4, 0, 2),
+            &Location::new(4, 0, 3),
+            &footprint,
+            false,
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(cost, 2);
+    }
+
+    #[test]
+    fn cubes_move_between_layers() {
+        let mut grid = Grid::new(4, 4, 4);
+
+        for (x, z) in (0..4).flat_map(|x| (0..4).map(move |z| (x, z))) {
+            grid.toggle_cell_obstruction(&Location::new(x, 1, z))
+                .unwrap();
+        }
+
+        let footprint = Footprint::cube(2).unwrap();
+        let start = Location::new(0, 0, 0);
+
+        assert!(!fits(&grid, &footprint, &start));
+        assert!(fits(&grid, &footprint, &Location::new(2, 2, 2)));
+        assert!(footprint_range_3d(&grid, &start, &footprint, 4, false)
+            .unwrap()
+            .is_empty());
+
+        let (steps, cost) = footprint_path_3d(
+            &grid,
+            &Location::new(0, 2, 0),
+            &Location::new(2, 2, 2),
+            &footprint,
+            false,
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!((steps.len(), cost), (3, 2));
+
+        grid.toggle_cell_obstruction(&Location::new(3, 1, 3))
+            .unwrap();
+        assert_eq!(
+            footprint_path_3d(
+                &grid,
+                &Location::new(0, 2, 0),
+                &Location::new(2, 2, 2),
+                &footprint,
+                false
+            ),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn ramps_need_headroom() {
+        let mut grid = Grid::new(2, 1, 2);
+        let (start, goal) = (Location::new(0, 0, 0), Location::new(1, 1, 0));
+        let footprint = Footprint::square(1).unwrap();
+
+        grid.add_cell_connector_and_adjacent(&start, Direction::UE, Connector::ramp(1))
+            .unwrap();
+
+        assert_eq!(
+            footprint_path_3d(&grid, &start, &goal, &footprint, false)
+                .map(|path| path.map(|p| p.1)),
+            Ok(Some(1))
+        );
+
+        grid.add_cell_boundry_and_adjacent(&start, Direction::U, Boundry::Full)
+            .unwrap();
+
+        assert_eq!(
+            footprint_path_3d(&grid, &start, &goal, &footprint, false),
+            Ok(None)
+        );
+    }
+
+    #[fixture]
+    fn gap() -> Grid {
+        Grid::from_ascii(
+            "\
++ + + + + +
+ . . . . .
++ + + + + +
+ . . . . .
++ + + + + +
+ # # . # #
++ + + + + +
+ . . . . .
++ + + + + +
+ . . . . .
++ + + + + +
+",
+        )
+        .unwrap()
+    }
+
+    #[rstest]
+    fn squeezing_is_opt_in(gap: Grid) {
+        let footprint = CreatureSize::Large.into();
+        let (start, goal) = (Location::new(0, 0, 0), Location::new(0, 0, 3));
+
+        assert_eq!(
+            footprint_path_2d(&gap, &start, &goal, &footprint, false),
+            Ok(None)
+        );
+        assert!(footprint_path_2d(&gap, &start, &goal, &footprint, true)
+            .unwrap()
+            .is_some());
+    }
+
+    #[rstest]
+    fn squeezed_steps_cost_double(gap: Grid) {
+        let footprint = CreatureSize::Large.into();
+        let (start, goal) = (Location::new(2, 0, 0), Location::new(2, 0, 3));
+
+        let (steps, cost) = footprint_path_2d(&gap, &start, &goal, &footprint, true)
+            .unwrap()
+            .unwrap();
+
+        let squeezed: Vec<_> = steps
+            .iter()
+            .filter(|step| step.squeezed)
+            .map(|step| step.anchor)
+            .collect();
+
+        assert_eq!(
+            squeezed,
+            vec![Location::new(2, 0, 1), Location::new(2, 0, 2)]
+        );
+        assert_eq!(cost, 5);
+        assert!(steps
+            .iter()
+            .all(|step| step.squeezed != fits(&gap, &footprint, &step.anchor)));
+    }
+
+    #[rstest]
+    #[case(CreatureSize::Medium, false)]
+    #[case(CreatureSize::Large, true)]
+    fn only_large_creatures_squeeze(gap: Grid, #[case] size: CreatureSize, #[case] widens: bool) {
+        let footprint = size.into();
+        let start = Location::new(2, 0, 0);
+
+        let range = footprint_range_2d(&gap, &start, &footprint, 2, false).unwrap();
+        let squeezing = footprint_range_2d(&gap, &start, &footprint, 2, true).unwrap();
+
+        assert!(range.is_subset(&squeezing));
+        assert_eq!(range != squeezing, widens);
+    }
+}
@@ -1,10 +1,13 @@
 pub mod dijkstra_max_move;
+pub mod footprint;
+pub mod modes;
+pub mod weighted;
 
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
-struct BHeapState<T: Eq>(usize, T);
+struct BHeapState<T: Eq, C: Ord = usize>(C, T);
 
-impl<T: Eq> BHeapState<T> {
-    pub fn new(weight: usize, item: T) -> Self {
-        BHeapState::<T>(weight, item)
+impl<T: Eq, C: Ord> BHeapState<T, C> {
+    pub fn new(weight: C, item: T) -> Self {
+        BHeapState(weight, item)
     }
 }
@@ -0,0 +1,562 @@
+use crate::prelude::{Boundry, Direction, Grid, GridError, Jump, Location, MovementMode, Speeds};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use super::BHeapState;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ModeStep {
+    pub to: Location,
+    pub mode: MovementMode,
+    pub fall: Option<i8>,
+    pub jump: bool,
+}
+
+// Locations a creature can reach on its layer with any mix of its movement modes.
+pub fn mode_range_2d(
+    grid: &Grid,
+    start: &Location,
+    speeds: &Speeds,
+) -> Result<HashSet<Location>, GridError> {
+    Ok(search(grid, start, None, speeds, false)?
+        .into_keys()
+        .filter(|loc| loc != start)
+        .collect())
+}
+
+pub fn mode_range_3d(
+    grid: &Grid,
+    start: &Location,
+    speeds: &Speeds,
+) -> Result<HashSet<Location>, GridError> {
+    Ok(search(grid, start, None, speeds, true)?
+        .into_keys()
+        .filter(|loc| loc != start)
+        .collect())
+}
+
+// The cheapest steps from `start` to `goal` within the creature's speeds, each with
+// the mode it is taken in, and their total cost.
+pub fn mode_path_2d(
+    grid: &Grid,
+    start: &Location,
+    goal: &Location,
+    speeds: &Speeds,
+) -> Result<Option<(Vec<ModeStep>, usize)>, GridError> {
+    path(grid, start, goal, speeds, false)
+}
+
+pub fn mode_path_3d(
+    grid: &Grid,
+    start: &Location,
+    goal: &Location,
+    speeds: &Speeds,
+) -> Result<Option<(Vec<ModeStep>, usize)>, GridError> {
+    path(grid, start, goal, speeds, true)
+}
+
+fn path(
+    grid: &Grid,
+    start: &Location,
+    goal: &Location,
+    speeds: &Speeds,
+    is_3d: bool,
+) -> Result<Option<(Vec<ModeStep>, usize)>, GridError> {
+    let visited = search(grid, start, Some(goal), speeds, is_3d)?;
+
+    let cost = match visited.get(goal) {
+        Some(visit) => visit.cost,
+        None => return Ok(None),
+    };
+
+    let mut steps = vec![];
+    let mut loc = *goal;
+
+    while let Some((previous, step)) = visited.get(&loc).and_then(|visit| visit.from) {
+        steps.push(step);
+        loc = previous;
+    }
+
+    steps.reverse();
+
+    Ok(Some((steps, cost)))
+}
+
+struct Visit {
+    cost: usize,
+    from: Option<(Location, ModeStep)>,
+}
+
+// Dijkstra over locations. Everything spent counts against every mode, so reaching
+// a location more cheaply never closes off a mode, and the cheapest cost per
+// location is all the search has to keep. Walkers only step onto cells they can
+// stand on; in 3D, stepping off a ledge instead falls to where they land. Walkers
+// may also jump, in 2D only across. Terrain ends movement there when it stops the
+// mode the creature entered it in.
+fn search(
+    grid: &Grid,
+    start: &Location,
+    goal: Option<&Location>,
+    speeds: &Speeds,
+    is_3d: bool,
+) -> Result<HashMap<Location, Visit>, GridError> {
+    grid.get_ref_cell(start)?;
+
+    let mut visited = HashMap::new();
+    let mut heap = BinaryHeap::new();
+
+    heap.push(Reverse(BHeapState::new(0, *start)));
+    visited.insert(
+        *start,
+        Visit {
+            cost: 0,
+            from: None,
+        },
+    );
+
+    while let Some(Reverse(BHeapState(cost, loc))) = heap.pop() {
+        if cost > visited[&loc].cost {
+            continue;
+        }
+
+        if goal == Some(&loc) {
+            break;
+        }
+
+        if let Some((_, step)) = visited[&loc].from {
+            if grid.stops_movement(&loc, step.mode) {
+                continue;
+            }
+        }
+
+        let neighbors: Vec<Location> = match is_3d {
+            true => grid.neighbors_3d_iter(loc).collect(),
+            false => grid.neighbors_2d_iter(loc).collect(),
+        };
+
+        for next in neighbors {
+            let mut best: Option<(usize, MovementMode)> = None;
+            let mut fall = None;
+
+            for mode in speeds.modes() {
+                let new_cost = match grid.cost_for_mode(&loc, &next, mode) {
+                    Ok(step_cost) => cost + step_cost,
+                    Err(GridError::BlockedByWall(..))
+                    | Err(GridError::Obstructed(_))
+                    | Err(GridError::ModeNotAllowed(..)) => continue,
+                    Err(err) => return Err(err),
+                };
+
+                if new_cost > speeds.speed(mode) {
+                    continue;
+                }
+
+                if mode == MovementMode::Walk && !grid.is_standable(&next) {
+                    fall = Some(new_cost).filter(|_| is_3d);
+                    continue;
+                }
+
+                if best.is_none_or(|(best, _)| new_cost < best) {
+                    best = Some((new_cost, mode));
+                }
+            }
+
+            if let Some((new_cost, mode)) = best {
+                let step = ModeStep {
+                    to: next,
+                    mode,
+                    fall: None,
+                    jump: false,
+                };
+                visit(&mut visited, &mut heap, loc, step, new_cost);
+            }
+
+            if let Some(new_cost) = fall {
+                let (landing, distance) = grid.landing(&next)?;
+                let step = ModeStep {
+                    to: landing,
+                    mode: MovementMode::Walk,
+                    fall: Some(distance),
+                    jump: false,
+                };
+                visit(&mut visited, &mut heap, loc, step, new_cost);
+            }
+        }
+
+        if speeds.walk == 0 {
+            continue;
+        }
+
+        for (landing, jump_cost) in jumps(grid, &loc, &speeds.jump, is_3d)? {
+            let new_cost = cost + jump_cost;
+
+            if new_cost <= speeds.walk {
+                let step = ModeStep {
+                    to: landing,
+                    mode: MovementMode::Walk,
+                    fall: None,
+                    jump: true,
+                };
+                visit(&mut visited, &mut heap, loc, step, new_cost);
+            }
+        }
+    }
+
+    Ok(visited)
+}
+
+// Where a jump from `from` can land, with what it costs. The jumper rises straight
+// up first and then travels in one compass direction, and the air along the way
+// must be clear of obstructions, Full walls and ceilings. Single steps are left to
+// walking.
+fn jumps(
+    grid: &Grid,
+    from: &Location,
+    jump: &Jump,
+    is_3d: bool,
+) -> Result<Vec<(Location, usize)>, GridError> {
+    let mut landings = vec![];
+
+    if !grid.is_standable(from) {
+        return Ok(landings);
+    }
+
+    let height = if is_3d { jump.height } else { 0 };
+    let mut top = *from;
+
+    for rise in 0..=height {
+        if rise > 0 {
+            let up = top.get_loc_from_dir(&Direction::U);
+
+            match grid.get_ref_cell(&up) {
+                Ok(cell) if !cell.is_obstructed => {}
+                _ => break,
+            }
+
+            if grid.boundry_between(&top, &up)? == Some(Boundry::Full) {
+                break;
+            }
+
+            top = up;
+        }
+
+        for dir in Direction::all_2d() {
+            let mut loc = top;
+
+            for run in 1..=jump.distance {
+                let next = loc.get_loc_from_dir(&dir);
+
+                if !grid.in_bounds(&next) || grid.boundry_cost_2d(&loc, &next).is_err() {
+                    break;
+                }
+
+                loc = next;
+
+                let walkable = grid.terrain_rules(&loc)?.cost(MovementMode::Walk).is_some();
+
+                if (run > 1 || rise > 0) && walkable && grid.is_standable(&loc) {
+                    landings.push((loc, (run + rise) as usize));
+                }
+            }
+        }
+    }
+
+    Ok(landings)
+}
+
+fn visit(
+    visited: &mut HashMap<Location, Visit>,
+    heap: &mut BinaryHeap<Reverse<BHeapState<Location>>>,
+    from: Location,
+    step: ModeStep,
+    cost: usize,
+) {
+    if visited.get(&step.to).is_none_or(|visit| cost < visit.cost) {
+        visited.insert(
+            step.to,
+            Visit {
+                cost,
+                from: Some((from, step)),
+            },
+        );
+        heap.push(Reverse(BHeapState::new(cost, step.to)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::{path_2d, Connector, Terrain};
+    use rstest::{fixture, rstest};
+
+    #[fixture]
+    fn grid() -> Grid {
+        let mut grid = Grid::from_ascii(
+            "\
++ + + + + + +
+ . . % % . .
++ + + + + + +
+ . ~ ~ ~ . .
++ + + + + + +
+ . . . . . #
++ + + + + + +
+
++ + + + + + +
+ . . . . . .
++ + + + + + +
+ . . . . . .
++ + + + + + +
+ . . . . . .
++ + + + + + +
+",
+        )
+        .unwrap();
+
+        // Floors under the upper layer, with a hole above the bottom left corner.
+        for (x, z) in (0..6).flat_map(|x| (0..3).map(move |z| (x, z))) {
+            if (x, z) != (0, 2) {
+                grid.add_cell_boundry_and_adjacent(
+                    &Location::new(x, 0, z),
+                    Direction::U,
+                    Boundry::Full,
+                )
+                .unwrap();
+            }
+        }
+
+        grid
+    }
+
+    #[rstest]
+    fn walking_matches_path_2d(grid: Grid) {
+        let start = Location::new(0, 0, 2);
+
+        assert_eq!(
+            mode_range_2d(&grid, &start, &Speeds::walking(2)).unwrap(),
+            path_2d(&grid, &start, 2).unwrap()
+        );
+    }
+
+    #[rstest]
+    fn swimmers_cross_water(grid: Grid) {
+        let (start, goal) = (Location::new(1, 0, 0), Location::new(4, 0, 0));
+
+        assert_eq!(
+            mode_path_2d(&grid, &start, &goal, &Speeds::walking(3)),
+            Ok(None)
+        );
+
+        let speeds = Speeds {
+            swim: 2,
+            ..Speeds::walking(3)
+        };
+        let (steps, cost) = mode_path_2d(&grid, &start, &goal, &speeds)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(cost, 3);
+        assert_eq!(
+            steps.iter().map(|step| step.mode).collect::<Vec<_>>(),
+            vec![MovementMode::Swim, MovementMode::Swim, MovementMode::Walk]
+        );
+    }
+
+    #[rstest]
+    #[case(Speeds::walking(3), false)]
+    #[case(Speeds { fly: 3, ..Speeds::default() }, true)]
+    fn flying_ignores_difficult_terrain(
+        grid: Grid,
+        #[case] speeds: Speeds,
+        #[case] reachable: bool,
+    ) {
+        let (start, goal) = (Location::new(0, 0, 1), Location::new(3, 0, 1));
+
+        assert_eq!(
+            mode_range_2d(&grid, &start, &speeds)
+                .unwrap()
+                .contains(&goal),
+            reachable
+        );
+    }
+
+    #[rstest]
+    #[case(Speeds::walking(4), false)]
+    #[case(Speeds { fly: 4, ..Speeds::default() }, true)]
+    fn flying_crosses_lava(mut grid: Grid, #[case] speeds: Speeds, #[case] crossed: bool) {
+        let (start, goal) = (Location::new(0, 0, 2), Location::new(2, 0, 2));
+        let lava = Location::new(1, 0, 2);
+
+        for z in 0..3 {
+            grid.set_cell_terrain(&Location::new(1, 0, z), Terrain::Lava)
+                .unwrap();
+        }
+
+        let range = mode_range_2d(&grid, &start, &speeds).unwrap();
+        assert!(range.contains(&lava));
+        assert_eq!(range.contains(&goal), crossed);
+    }
+
+    #[rstest]
+    fn climbing_changes_layers(grid: Grid) {
+        let start = Location::new(0, 0, 2);
+        let goal = Location::new(3, 1, 2);
+
+        assert_eq!(
+            mode_path_3d(&grid, &start, &goal, &Speeds::walking(6)),
+            Ok(None)
+        );
+
+        let speeds = Speeds {
+            climb: 1,
+            ..Speeds::walking(6)
+        };
+        let (steps, cost) = mode_path_3d(&grid, &start, &goal, &speeds)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(cost, 4);
+        assert_eq!(steps[0].to, Location::new(0, 1, 2));
+        assert_eq!(steps[0].mode, MovementMode::Climb);
+        assert!(steps[1..]
+            .iter()
+            .all(|step| step.mode == MovementMode::Walk));
+    }
+
+    #[rstest]
+    fn walkers_take_stairs(mut grid: Grid) {
+        let start = Location::new(0, 0, 2);
+        let goal = Location::new(3, 1, 2);
+
+        grid.add_cell_connector_and_adjacent(&start, Direction::U, Connector::stairs(2))
+            .unwrap();
+
+        let (steps, cost) = mode_path_3d(&grid, &start, &goal, &Speeds::walking(6))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(cost, 5);
+        assert_eq!(steps[0].to, Location::new(0, 1, 2));
+        assert!(steps.iter().all(|step| step.mode == MovementMode::Walk));
+    }
+
+    #[rstest]
+    fn walkers_fall_off_ledges(grid: Grid) {
+        let (start, hole, landing) = (
+            Location::new(1, 1, 2),
+            Location::new(0, 1, 2),
+            Location::new(0, 0, 2),
+        );
+
+        assert_eq!(
+            mode_path_3d(&grid, &start, &landing, &Speeds::walking(6)),
+            Ok(Some((
+                vec![ModeStep {
+                    to: landing,
+                    mode: MovementMode::Walk,
+                    fall: Some(1),
+                    jump: false,
+                }],
+                1
+            )))
+        );
+
+        let range = mode_range_3d(&grid, &start, &Speeds::walking(6)).unwrap();
+        assert!(range.contains(&landing) && !range.contains(&hole));
+        assert!(!mode_range_2d(&grid, &start, &Speeds::walking(6))
+            .unwrap()
+            .contains(&hole));
+
+        let speeds = Speeds {
+            fly: 1,
+            ..Speeds::walking(6)
+        };
+        assert!(mode_range_3d(&grid, &start, &speeds)
+            .unwrap()
+            .contains(&hole));
+    }
+
+    #[rstest]
+    fn long_jumps_cross_gaps(mut grid: Grid) {
+        let (start, goal) = (Location::new(1, 0, 0), Location::new(4, 0, 0));
+        let speeds = Speeds {
+            jump: Jump {
+                distance: 3,
+                height: 0,
+            },
+            ..Speeds::walking(6)
+        };
+
+        assert_eq!(
+            mode_path_2d(&grid, &start, &goal, &Speeds::walking(6)).map(|path| path.map(|p| p.1)),
+            Ok(Some(5))
+        );
+        assert_eq!(
+            mode_path_2d(&grid, &start, &goal, &speeds),
+            Ok(Some((
+                vec![ModeStep {
+                    to: goal,
+                    mode: MovementMode::Walk,
+                    fall: None,
+                    jump: true,
+                }],
+                3
+            )))
+        );
+
+        grid.add_cell_boundry_and_adjacent(&Location::new(2, 0, 0), Direction::E, Boundry::Full)
+            .unwrap();
+
+        let (steps, cost) = mode_path_2d(&grid, &start, &goal, &speeds)
+            .unwrap()
+            .unwrap();
+        assert_eq!(cost, 4);
+        assert_eq!(steps[0].to, Location::new(3, 0, 2));
+    }
+
+    #[rstest]
+    fn high_jumps_reach_ledges(grid: Grid) {
+        let (start, goal) = (Location::new(0, 0, 2), Location::new(1, 1, 2));
+        let speeds = Speeds {
+            jump: Jump {
+                distance: 1,
+                height: 1,
+            },
+            ..Speeds::walking(2)
+        };
+
+        assert_eq!(
+            mode_path_3d(&grid, &start, &goal, &Speeds::walking(6)),
+            Ok(None)
+        );
+
+        let (steps, cost) = mode_path_3d(&grid, &start, &goal, &speeds)
+            .unwrap()
+            .unwrap();
+        assert_eq!(cost, 2);
+        assert!(steps.len() == 1 && steps[0].jump);
+
+        assert!(mode_range_3d(&grid, &Location::new(1, 0, 1), &speeds)
+            .unwrap()
+            .iter()
+            .all(|loc| loc.y == 0));
+    }
+
+    #[rstest]
+    #[case(2, false)]
+    #[case(3, true)]
+    fn spent_movement_counts_against_every_mode(#[case] climb: usize, #[case] reachable: bool) {
+        let grid =
+            Grid::from_ascii("+ + + +\n . . .\n+ + + +\n\n+ + + +\n # # .\n+ + + +").unwrap();
+        let speeds = Speeds {
+            climb,
+            ..Speeds::walking(6)
+        };
+
+        assert_eq!(
+            mode_range_3d(&grid, &Location::new(0, 0, 0), &speeds)
+                .unwrap()
+                .contains(&Location::new(2, 1, 0)),
+            reachable
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use crate::prelude::{Cost, Grid, GridError, Location, MovementMode};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use super::BHeapState;
+
+// `path_2d` with the grid's weights, summed in whatever cost type the caller picks.
+pub fn weighted_range_2d<C: Cost>(
+    grid: &Grid,
+    start: &Location,
+    max_cost: C,
+) -> Result<HashSet<Location>, GridError> {
+    Ok(search(grid, start, None, Some(max_cost))?
+        .into_keys()
+        .filter(|loc| loc != start)
+        .collect())
+}
+
+// The cheapest walk from `start` to `goal`, without the start, and its total cost.
+pub fn weighted_path_2d<C: Cost>(
+    grid: &Grid,
+    start: &Location,
+    goal: &Location,
+) -> Result<Option<(Vec<Location>, C)>, GridError> {
+    let visited = search(grid, start, Some(goal), None)?;
+
+    let cost = match visited.get(goal) {
+        Some(visit) => visit.cost,
+        None => return Ok(None),
+    };
+
+    let mut steps = vec![];
+    let mut loc = *goal;
+
+    while let Some(previous) = visited.get(&loc).and_then(|visit| visit.previous) {
+        steps.push(loc);
+        loc = previous;
+    }
+
+    steps.reverse();
+
+    Ok(Some((steps, cost)))
+}
+
+struct Visit<C> {
+    cost: C,
+    previous: Option<Location>,
+}
+
+fn search<C: Cost>(
+    grid: &Grid,
+    start: &Location,
+    goal: Option<&Location>,
+    max_cost: Option<C>,
+) -> Result<HashMap<Location, Visit<C>>, GridError> {
+    grid.get_ref_cell(start)?;
+
+    let mut visited = HashMap::new();
+    let mut heap = BinaryHeap::new();
+
+    heap.push(Reverse(BHeapState::new(C::zero(), *start)));
+    visited.insert(
+        *start,
+        Visit {
+            cost: C::zero(),
+            previous: None,
+        },
+    );
+
+    while let Some(Reverse(BHeapState(cost, loc))) = heap.pop() {
+        if cost > visited[&loc].cost {
+            continue;
+        }
+
+        if goal == Some(&loc) {
+            break;
+        }
+
+        if loc != *start && grid.stops_movement(&loc, MovementMode::Walk) {
+            continue;
+        }
+
+        for next in grid.neighbors_2d_iter(loc) {
+            if !grid.is_standable(&next) {
+                continue;
+            }
+
+            let new_cost = match grid.weighted_cost(&loc, &next, MovementMode::Walk) {
+                Ok(step_cost) => cost + step_cost,
+                Err(GridError::BlockedByWall(..))
+                | Err(GridError::Obstructed(_))
+                | Err(GridError::ModeNotAllowed(..)) => continue,
+                Err(err) => return Err(err),
+            };
+
+            if max_cost.is_some_and(|max_cost| new_cost > max_cost) {
+                continue;
+            }
+
+            if visited.get(&next).is_none_or(|visit| new_cost < visit.cost) {
+                visited.insert(
+                    next,
+                    Visit {
+                        cost: new_cost,
+                        previous: Some(loc),
+                    },
+                );
+                heap.push(Reverse(BHeapState::new(new_cost, next)));
+            }
+        }
+    }
+
+    Ok(visited)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::{path_2d, Direction, FloatCost};
+    use rstest::{fixture, rstest};
+
+    #[fixture]
+    fn grid() -> Grid {
+        Grid::from_ascii(
+            "\
++ + + + +
+ . . . .
++ + + + +
+ . ~ . #
++ + + + +
+",
+        )
+        .unwrap()
+    }
+
+    #[rstest]
+    fn unweighted_grids_match_path_2d(grid: Grid) {
+        let start = Location::new(0, 0, 0);
+
+        assert_eq!(
+            weighted_range_2d(&grid, &start, 2usize).unwrap(),
+            path_2d(&grid, &start, 2).unwrap()
+        );
+    }
+
+    #[rstest]
+    fn fractional_weights_add_up(mut grid: Grid) {
+        let (start, goal) = (Location::new(0, 0, 0), Location::new(2, 0, 0));
+
+        for loc in [Location::new(1, 0, 0), goal].iter() {
+            grid.set_cell_weight(loc, Some(1.5)).unwrap();
+        }
+
+        let (_, cost) = weighted_path_2d::<FloatCost>(&grid, &start, &goal)
+            .unwrap()
+            .unwrap();
+        assert_eq!(cost, FloatCost(3.0));
+        assert!(weighted_range_2d(&grid, &start, FloatCost(3.0))
+            .unwrap()
+            .contains(&goal));
+
+        let (_, cost) = weighted_path_2d::<u32>(&grid, &start, &goal)
+            .unwrap()
+            .unwrap();
+        assert_eq!(cost, 4);
+        assert!(!weighted_range_2d(&grid, &start, 3u32)
+            .unwrap()
+            .contains(&goal));
+    }
+
+    #[rstest]
+    fn heavy_edges_are_avoided(mut grid: Grid) {
+        let (start, goal) = (Location::new(0, 0, 0), Location::new(2, 0, 0));
+
+        grid.set_edge_weight(&start, Direction::E, Some(5.0))
+            .unwrap();
+        grid.set_cell_weight(&Location::new(1, 0, 1), Some(1.0))
+            .unwrap();
+
+        assert_eq!(
+            weighted_path_2d::<FloatCost>(&grid, &start, &goal),
+            Ok(Some((vec![Location::new(1, 0, 1), goal], FloatCost(2.0))))
+        );
+    }
+
+    #[rstest]
+    fn unreachable_goals(grid: Grid) {
+        assert_eq!(
+            weighted_path_2d::<u32>(&grid, &Location::new(0, 0, 0), &Location::new(3, 0, 1)),
+            Ok(None)
+        );
+    }
+}
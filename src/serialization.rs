@@ -0,0 +1,210 @@
+use crate::prelude::{Boundry, Cell, Connector, Direction, Grid, Location, Terrain, TerrainRules};
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+
+pub use legion::serialize::{
+    set_entity_serializer, Canon, CustomEntitySerializer, EntitySerializer,
+};
+
+// Cell entities are written through legion's entity serializer, so grids holding
+// entities must be (de)serialized inside `set_entity_serializer` with a mapping
+// from `legion::Entity` to stable IDs, such as `Canon` or a `CustomEntitySerializer`.
+#[derive(Serialize)]
+struct GridRef<'a> {
+    length: i8,
+    width: i8,
+    height: i8,
+    cells: Vec<&'a Cell>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    terrains: Vec<(Terrain, &'a TerrainRules)>,
+}
+
+#[derive(Deserialize)]
+struct GridData {
+    length: i8,
+    width: i8,
+    height: i8,
+    cells: Vec<Cell>,
+    #[serde(default)]
+    terrains: Vec<(Terrain, TerrainRules)>,
+}
+
+// Cells as they are read. Saves from before terrains only say whether a cell is
+// difficult terrain or water.
+#[derive(Deserialize)]
+pub(crate) struct CellRecord {
+    loc: Location,
+    boundries: Option<HashMap<Direction, Boundry>>,
+    #[serde(default)]
+    connectors: Option<HashMap<Direction, Connector>>,
+    #[serde(default, deserialize_with = "present")]
+    terrain: Option<Terrain>,
+    #[serde(default)]
+    is_difficult_terrain: bool,
+    #[serde(default)]
+    is_water: bool,
+    is_obstructed: bool,
+    #[serde(default)]
+    weight: Option<f64>,
+    #[serde(default)]
+    edge_weights: Option<HashMap<Direction, f64>>,
+    entities: Vec<legion::Entity>,
+}
+
+fn present<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Terrain>, D::Error> {
+    Terrain::deserialize(deserializer).map(Some)
+}
+
+impl From<CellRecord> for Cell {
+    fn from(record: CellRecord) -> Self {
+        let terrain = match record.terrain {
+            Some(terrain) => terrain,
+            None if record.is_water => Terrain::Water,
+            None if record.is_difficult_terrain => Terrain::Difficult,
+            None => Terrain::Floor,
+        };
+
+        Cell {
+            loc: record.loc,
+            boundries: record.boundries,
+            connectors: record.connectors,
+            terrain,
+            is_obstructed: record.is_obstructed,
+            weight: record.weight,
+            edge_weights: record.edge_weights,
+            entities: record.entities,
+        }
+    }
+}
+
+impl Serialize for Grid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut cells: Vec<&Cell> = self.cells_iter().collect();
+        cells.sort_by_key(|cell| cell.loc);
+
+        GridRef {
+            length: self.length,
+            width: self.width,
+            height: self.height,
+            cells,
+            terrains: self.terrain_table().overrides(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Grid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = GridData::deserialize(deserializer)?;
+
+        let mut grid = Grid::from_cells(data.length, data.width, data.height, data.cells)
+            .map_err(D::Error::custom)?;
+
+        for (terrain, rules) in data.terrains {
+            grid.define_terrain(terrain, rules);
+        }
+
+        Ok(grid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::{GridError, MovementMode};
+    use legion::World;
+    use rstest::{fixture, rstest};
+
+    #[fixture]
+    fn grid() -> Grid {
+        let mut grid = Grid::new(4, 3, 2);
+        grid.add_cell_boundry_and_adjacent(&Location::new(1, 0, 1), Direction::E, Boundry::Full)
+            .unwrap();
+        grid.add_cell_boundry_and_adjacent(&Location::new(2, 1, 0), Direction::SW, Boundry::Half)
+            .unwrap();
+        grid.toggle_cell_obstruction(&Location::new(3, 0, 2))
+            .unwrap();
+        grid.toggle_cell_difficult_terrain(&Location::new(0, 1, 1))
+            .unwrap();
+        grid.add_cell_connector_and_adjacent(
+            &Location::new(2, 0, 2),
+            Direction::UN,
+            Connector::ramp(2),
+        )
+        .unwrap();
+        grid.set_cell_weight(&Location::new(1, 1, 2), Some(1.5))
+            .unwrap();
+        grid.set_edge_weight(&Location::new(0, 0, 0), Direction::S, Some(2.5))
+            .unwrap();
+        grid.set_cell_terrain(&Location::new(3, 1, 2), Terrain::Custom(1))
+            .unwrap();
+        grid.define_terrain(
+            Terrain::Custom(1),
+            TerrainRules {
+                costs: vec![(MovementMode::Walk, 3)].into_iter().collect(),
+                stops: vec![MovementMode::Walk].into_iter().collect(),
+                tags: vec!["sticky".to_string()].into_iter().collect(),
+            },
+        );
+
+        grid
+    }
+
+    #[rstest]
+    fn json_round_trip(mut grid: Grid) {
+        let mut world = World::default();
+        let entity = world.push(());
+        grid.add_entity(&Location::new(2, 0, 2), entity).unwrap();
+
+        let canon = Canon::default();
+        let json = set_entity_serializer(&canon, || serde_json::to_string(&grid).unwrap());
+        let result: Grid = set_entity_serializer(&canon, || serde_json::from_str(&json).unwrap());
+
+        assert_eq!(result, grid);
+        assert_eq!(
+            result.entity_location(&entity),
+            Some(Location::new(2, 0, 2))
+        );
+    }
+
+    #[rstest]
+    fn ron_round_trip(grid: Grid) {
+        let text = ron::to_string(&grid).unwrap();
+        let result: Grid = ron::from_str(&text).unwrap();
+
+        assert_eq!(result, grid);
+    }
+
+    #[test]
+    fn cells_outside_of_the_grid_are_rejected() {
+        let json = r#"{"length":1,"width":1,"height":1,"cells":[
+            {"loc":{"x":2,"y":0,"z":0},"boundries":null,"is_difficult_terrain":false,"is_obstructed":false,"entities":[]}
+        ]}"#;
+
+        assert!(serde_json::from_str::<Grid>(json).is_err());
+    }
+
+    #[test]
+    fn negative_sizes_are_rejected() {
+        let json = r#"{"length":-1,"width":1,"height":1,"cells":[]}"#;
+
+        let err = serde_json::from_str::<Grid>(json).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains(&GridError::InvalidSize(-1, 1, 1).to_string()));
+    }
+
+    #[test]
+    fn difficult_terrain_from_older_saves() {
+        let json = r#"{"length":1,"width":1,"height":1,"cells":[
+            {"loc":{"x":0,"y":0,"z":0},"boundries":null,"is_difficult_terrain":true,"is_obstructed":false,"entities":[]}
+        ]}"#;
+
+        let grid = serde_json::from_str::<Grid>(json).unwrap();
+
+        assert_eq!(
+            grid.get_ref_cell(&Location::new(0, 0, 0)).unwrap().terrain,
+            Terrain::Difficult
+        );
+    }
+}
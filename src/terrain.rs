@@ -0,0 +1,204 @@
+use crate::prelude::MovementMode;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Terrain {
+    #[default]
+    Floor,
+    Difficult,
+    Water,
+    DeepWater,
+    Lava,
+    Ice,
+    Rubble,
+    Vegetation,
+    Custom(u16),
+}
+
+// What entering a cell of some terrain costs in each movement mode; modes without a
+// cost cannot enter it at all. Movement in the modes the terrain stops ends there.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TerrainRules {
+    pub costs: HashMap<MovementMode, usize>,
+    pub stops: HashSet<MovementMode>,
+    pub tags: HashSet<String>,
+}
+
+impl TerrainRules {
+    pub fn cost(&self, mode: MovementMode) -> Option<usize> {
+        self.costs.get(&mode).copied()
+    }
+
+    pub fn stops_movement(&self, mode: MovementMode) -> bool {
+        self.stops.contains(&mode)
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.contains(tag)
+    }
+}
+
+// Rules for every terrain on a grid. The built in terrains start with the rules
+// below and can be redefined; custom terrains behave like floor until defined.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TerrainTable {
+    rules: HashMap<Terrain, TerrainRules>,
+}
+
+impl TerrainTable {
+    pub fn get(&self, terrain: &Terrain) -> &TerrainRules {
+        self.rules
+            .get(terrain)
+            .unwrap_or_else(|| &self.rules[&Terrain::Floor])
+    }
+
+    pub fn define(&mut self, terrain: Terrain, rules: TerrainRules) {
+        self.rules.insert(terrain, rules);
+    }
+
+    // The rules that differ from the built in ones, which is all a save has to keep.
+    pub(crate) fn overrides(&self) -> Vec<(Terrain, &TerrainRules)> {
+        let defaults = TerrainTable::default();
+
+        let mut overrides = self
+            .rules
+            .iter()
+            .filter(|(terrain, rules)| defaults.rules.get(terrain) != Some(rules))
+            .map(|(terrain, rules)| (*terrain, rules))
+            .collect::<Vec<(Terrain, &TerrainRules)>>();
+
+        overrides.sort_unstable_by_key(|(terrain, _)| *terrain);
+        overrides
+    }
+}
+
+impl Default for TerrainTable {
+    fn default() -> Self {
+        use MovementMode::*;
+
+        let rules = vec![
+            (
+                Terrain::Floor,
+                rules(&[(Walk, 1), (Fly, 1), (Climb, 1), (Burrow, 1)], &[], &[]),
+            ),
+            (
+                Terrain::Difficult,
+                rules(
+                    &[(Walk, 2), (Fly, 1), (Climb, 2), (Burrow, 1)],
+                    &["difficult"],
+                    &[],
+                ),
+            ),
+            (
+                Terrain::Water,
+                rules(&[(Fly, 1), (Swim, 1)], &["water"], &[]),
+            ),
+            (
+                Terrain::DeepWater,
+                rules(&[(Fly, 1), (Swim, 2)], &["water", "deep"], &[]),
+            ),
+            (
+                Terrain::Lava,
+                rules(
+                    &[(Walk, 2), (Fly, 1), (Climb, 2)],
+                    &["hazard", "fire"],
+                    &[Walk, Climb],
+                ),
+            ),
+            (
+                Terrain::Ice,
+                rules(
+                    &[(Walk, 2), (Fly, 1), (Climb, 2), (Burrow, 2)],
+                    &["difficult", "slippery"],
+                    &[],
+                ),
+            ),
+            (
+                Terrain::Rubble,
+                rules(
+                    &[(Walk, 2), (Fly, 1), (Climb, 2), (Burrow, 2)],
+                    &["difficult"],
+                    &[],
+                ),
+            ),
+            (
+                Terrain::Vegetation,
+                rules(
+                    &[(Walk, 2), (Fly, 1), (Climb, 2), (Burrow, 1)],
+                    &["difficult", "plant"],
+                    &[],
+                ),
+            ),
+        ];
+
+        TerrainTable {
+            rules: rules.into_iter().collect(),
+        }
+    }
+}
+
+fn rules(costs: &[(MovementMode, usize)], tags: &[&str], stops: &[MovementMode]) -> TerrainRules {
+    TerrainRules {
+        costs: costs.iter().copied().collect(),
+        stops: stops.iter().copied().collect(),
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(Terrain::Floor, MovementMode::Walk, Some(1))]
+    #[case(Terrain::Difficult, MovementMode::Walk, Some(2))]
+    #[case(Terrain::Difficult, MovementMode::Fly, Some(1))]
+    #[case(Terrain::Water, MovementMode::Walk, None)]
+    #[case(Terrain::DeepWater, MovementMode::Swim, Some(2))]
+    #[case(Terrain::Custom(7), MovementMode::Walk, Some(1))]
+    fn default_costs(
+        #[case] terrain: Terrain,
+        #[case] mode: MovementMode,
+        #[case] expected: Option<usize>,
+    ) {
+        assert_eq!(TerrainTable::default().get(&terrain).cost(mode), expected);
+    }
+
+    #[rstest]
+    #[case(MovementMode::Walk, true)]
+    #[case(MovementMode::Climb, true)]
+    #[case(MovementMode::Fly, false)]
+    fn lava_stops_everything_but_flying(#[case] mode: MovementMode, #[case] expected: bool) {
+        assert_eq!(
+            TerrainTable::default()
+                .get(&Terrain::Lava)
+                .stops_movement(mode),
+            expected
+        );
+    }
+
+    #[test]
+    fn custom_terrains_can_be_defined() {
+        let mut table = TerrainTable::default();
+        let quicksand = Terrain::Custom(1);
+
+        table.define(
+            quicksand,
+            rules(
+                &[(MovementMode::Walk, 3)],
+                &["sticky"],
+                &[MovementMode::Walk],
+            ),
+        );
+
+        let rules = table.get(&quicksand);
+        assert_eq!(rules.cost(MovementMode::Walk), Some(3));
+        assert_eq!(rules.cost(MovementMode::Fly), None);
+        assert!(rules.stops_movement(MovementMode::Walk) && rules.has_tag("sticky"));
+        assert!(!rules.stops_movement(MovementMode::Fly));
+        assert_eq!(table.get(&Terrain::Custom(2)), table.get(&Terrain::Floor));
+    }
+}
@@ -0,0 +1,154 @@
+use super::{Properties, Property, TiledError, TiledLayer, TiledMap, TiledObject};
+use serde_json::Value;
+use std::collections::HashMap;
+
+pub(super) fn parse(text: &str) -> Result<TiledMap, TiledError> {
+    let map: Value =
+        serde_json::from_str(text).map_err(|err| TiledError::Parse(err.to_string()))?;
+
+    if map["infinite"].as_bool() == Some(true) {
+        return Err(TiledError::Unsupported("infinite maps".to_string()));
+    }
+
+    let mut tiles = HashMap::new();
+
+    for tileset in map["tilesets"].as_array().into_iter().flatten() {
+        if tileset.get("source").is_some() {
+            return Err(TiledError::Unsupported("external tilesets".to_string()));
+        }
+
+        let first_gid = uint(tileset, "firstgid")?;
+
+        for tile in tileset["tiles"].as_array().into_iter().flatten() {
+            let gid = first_gid
+                .checked_add(uint(tile, "id")?)
+                .ok_or_else(|| TiledError::Parse("tile id out of range".to_string()))?;
+            tiles.insert(gid, properties(tile)?);
+        }
+    }
+
+    let mut layers = vec![];
+    parse_layers(array(&map, "layers")?, &mut layers)?;
+
+    Ok(TiledMap {
+        width: uint(&map, "width")?,
+        height: uint(&map, "height")?,
+        tile_width: number(&map, "tilewidth")?,
+        tile_height: number(&map, "tileheight")?,
+        tiles,
+        layers,
+    })
+}
+
+// Group layers are flattened, keeping the order their layers are drawn in.
+fn parse_layers(values: &[Value], layers: &mut Vec<TiledLayer>) -> Result<(), TiledError> {
+    for layer in values.iter() {
+        match layer["type"].as_str() {
+            Some("tilelayer") => {
+                if layer["encoding"]
+                    .as_str()
+                    .is_some_and(|encoding| encoding != "csv")
+                {
+                    return Err(TiledError::Unsupported(
+                        "tile layer encodings other than csv".to_string(),
+                    ));
+                }
+
+                let data = array(layer, "data")?
+                    .iter()
+                    .map(|gid| {
+                        gid.as_u64()
+                            .map(|gid| gid as u32)
+                            .ok_or_else(|| TiledError::Parse("invalid tile gid".to_string()))
+                    })
+                    .collect::<Result<Vec<u32>, TiledError>>()?;
+
+                layers.push(TiledLayer::Tiles {
+                    properties: properties(layer)?,
+                    data,
+                });
+            }
+            Some("objectgroup") => {
+                let objects = array(layer, "objects")?
+                    .iter()
+                    .map(object)
+                    .collect::<Result<Vec<TiledObject>, TiledError>>()?;
+
+                layers.push(TiledLayer::Objects {
+                    properties: properties(layer)?,
+                    objects,
+                });
+            }
+            Some("group") => parse_layers(array(layer, "layers")?, layers)?,
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn object(value: &Value) -> Result<TiledObject, TiledError> {
+    let polyline = match value.get("polyline") {
+        Some(points) => Some(
+            points
+                .as_array()
+                .ok_or_else(|| TiledError::Parse("invalid polyline".to_string()))?
+                .iter()
+                .map(|point| Ok((number(point, "x")?, number(point, "y")?)))
+                .collect::<Result<Vec<(f32, f32)>, TiledError>>()?,
+        ),
+        None => None,
+    };
+
+    Ok(TiledObject {
+        x: number(value, "x")?,
+        y: number(value, "y")?,
+        width: value["width"].as_f64().unwrap_or(0.0) as f32,
+        height: value["height"].as_f64().unwrap_or(0.0) as f32,
+        polyline,
+        properties: properties(value)?,
+    })
+}
+
+fn properties(value: &Value) -> Result<Properties, TiledError> {
+    let mut properties = HashMap::new();
+
+    for property in value["properties"].as_array().into_iter().flatten() {
+        let name = property["name"]
+            .as_str()
+            .ok_or_else(|| TiledError::Parse("property without a name".to_string()))?;
+
+        let value = match &property["value"] {
+            Value::Bool(value) => Property::Bool(*value),
+            Value::Number(value) if value.is_i64() => Property::Int(value.as_i64().unwrap()),
+            Value::Number(value) => Property::Text(value.to_string()),
+            Value::String(value) => Property::Text(value.clone()),
+            _ => continue,
+        };
+
+        properties.insert(name.to_string(), value);
+    }
+
+    Ok(properties)
+}
+
+fn array<'a>(value: &'a Value, key: &str) -> Result<&'a [Value], TiledError> {
+    value[key]
+        .as_array()
+        .map(|values| values.as_slice())
+        .ok_or_else(|| TiledError::Parse(format!("missing array {}", key)))
+}
+
+fn uint(value: &Value, key: &str) -> Result<u32, TiledError> {
+    value[key]
+        .as_u64()
+        .map(|value| value as u32)
+        .ok_or_else(|| TiledError::Parse(format!("missing integer {}", key)))
+}
+
+fn number(value: &Value, key: &str) -> Result<f32, TiledError> {
+    value[key]
+        .as_f64()
+        .map(|value| value as f32)
+        .ok_or_else(|| TiledError::Parse(format!("missing number {}", key)))
+}
@@ -0,0 +1,533 @@
+mod json;
+mod tmx;
+
+use crate::prelude::{Boundry, Direction, Grid, GridError, Location, Terrain};
+use std::{collections::HashMap, error::Error, fmt};
+
+// Tile flip flags live in the top bits of every gid in layer data.
+const GID_MASK: u32 = 0x1FFF_FFFF;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TiledError {
+    Parse(String),
+    Unsupported(String),
+    Grid(GridError),
+}
+
+impl fmt::Display for TiledError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TiledError::Parse(msg) => write!(f, "invalid tiled map: {}", msg),
+            TiledError::Unsupported(msg) => write!(f, "unsupported tiled map: {}", msg),
+            TiledError::Grid(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl Error for TiledError {}
+
+impl From<GridError> for TiledError {
+    fn from(err: GridError) -> Self {
+        TiledError::Grid(err)
+    }
+}
+
+// Names of the custom properties read from tiles, objects and layers. Boolean
+// properties mark cells as obstructed, difficult terrain or water, and `terrain`
+// names any terrain in snake case, or a custom one by number. Wall properties
+// take "full" or "half" (or `true` for a Full wall): on tiles and rectangle objects
+// the per-direction names put walls on that side of every covered cell, and on
+// polyline objects `wall` puts walls along the tile edges the polyline follows.
+// Tile layers map to `y` in order unless they set `level`; object layers apply to
+// the level of the tile layer before them unless they set it themselves.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TiledSchema {
+    pub obstructed: String,
+    pub difficult_terrain: String,
+    pub water: String,
+    pub terrain: String,
+    pub wall: String,
+    pub walls: Vec<(Direction, String)>,
+    pub level: String,
+}
+
+impl Default for TiledSchema {
+    fn default() -> Self {
+        TiledSchema {
+            obstructed: "obstructed".to_string(),
+            difficult_terrain: "difficult_terrain".to_string(),
+            water: "water".to_string(),
+            terrain: "terrain".to_string(),
+            wall: "wall".to_string(),
+            walls: vec![
+                (Direction::N, "wall_n".to_string()),
+                (Direction::E, "wall_e".to_string()),
+                (Direction::S, "wall_s".to_string()),
+                (Direction::W, "wall_w".to_string()),
+            ],
+            level: "level".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Property {
+    Bool(bool),
+    Int(i64),
+    Text(String),
+}
+
+type Properties = HashMap<String, Property>;
+
+struct TiledMap {
+    width: u32,
+    height: u32,
+    tile_width: f32,
+    tile_height: f32,
+    tiles: HashMap<u32, Properties>,
+    layers: Vec<TiledLayer>,
+}
+
+enum TiledLayer {
+    Tiles {
+        properties: Properties,
+        data: Vec<u32>,
+    },
+    Objects {
+        properties: Properties,
+        objects: Vec<TiledObject>,
+    },
+}
+
+struct TiledObject {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    polyline: Option<Vec<(f32, f32)>>,
+    properties: Properties,
+}
+
+impl Grid {
+    pub fn from_tiled_json(text: &str, schema: &TiledSchema) -> Result<Grid, TiledError> {
+        json::parse(text)?.build(schema)
+    }
+
+    pub fn from_tmx(text: &str, schema: &TiledSchema) -> Result<Grid, TiledError> {
+        tmx::parse(text)?.build(schema)
+    }
+}
+
+impl TiledMap {
+    fn build(&self, schema: &TiledSchema) -> Result<Grid, TiledError> {
+        let (length, width) = (to_i8(self.width as i64)?, to_i8(self.height as i64)?);
+
+        let mut levels = vec![];
+        let mut level = -1;
+
+        for layer in self.layers.iter() {
+            level = match (layer.properties().get(&schema.level), layer) {
+                (Some(Property::Int(value)), _) => *value,
+                (Some(_), _) => {
+                    return Err(TiledError::Parse(format!(
+                        "layer property {} must be an int",
+                        schema.level
+                    )))
+                }
+                (None, TiledLayer::Tiles { .. }) => level + 1,
+                (None, TiledLayer::Objects { .. }) => level.max(0),
+            };
+
+            levels.push(to_i8(level)?);
+        }
+
+        let height = levels.iter().max().map_or(1, |level| level + 1);
+        let mut grid = Grid::new(length, width, height);
+
+        for (layer, y) in self.layers.iter().zip(levels) {
+            match layer {
+                TiledLayer::Tiles { data, .. } => {
+                    if data.len() != (self.width * self.height) as usize {
+                        return Err(TiledError::Parse(format!(
+                            "tile layer holds {} tiles instead of {}",
+                            data.len(),
+                            self.width * self.height
+                        )));
+                    }
+
+                    for (idx, gid) in data.iter().enumerate() {
+                        let loc = Location::new(
+                            (idx % self.width as usize) as i8,
+                            y,
+                            (idx / self.width as usize) as i8,
+                        );
+
+                        if let Some(properties) = self.tiles.get(&(gid & GID_MASK)) {
+                            apply_properties(&mut grid, &loc, properties, schema)?;
+                        }
+                    }
+                }
+                TiledLayer::Objects { objects, .. } => {
+                    for object in objects.iter() {
+                        self.apply_object(&mut grid, y, object, schema)?;
+                    }
+                }
+            }
+        }
+
+        Ok(grid)
+    }
+
+    fn apply_object(
+        &self,
+        grid: &mut Grid,
+        y: i8,
+        object: &TiledObject,
+        schema: &TiledSchema,
+    ) -> Result<(), TiledError> {
+        if let Some(points) = &object.polyline {
+            let boundry = object
+                .properties
+                .get(&schema.wall)
+                .map(|property| to_boundry(&schema.wall, property))
+                .transpose()?
+                .flatten();
+
+            let boundry = match boundry {
+                Some(boundry) => boundry,
+                None => return Ok(()),
+            };
+
+            let corners = points
+                .iter()
+                .map(|(x, z)| {
+                    (
+                        ((object.x + x) / self.tile_width).round() as i32,
+                        ((object.y + z) / self.tile_height).round() as i32,
+                    )
+                })
+                .collect::<Vec<(i32, i32)>>();
+
+            for segment in corners.windows(2) {
+                add_polyline_walls(grid, y, segment[0], segment[1], boundry)?;
+            }
+
+            return Ok(());
+        }
+
+        // Rectangles cover every tile they overlap, which snaps them to the grid.
+        let min_x = (object.x / self.tile_width).floor() as i32;
+        let min_z = (object.y / self.tile_height).floor() as i32;
+        let max_x = ((object.x + object.width) / self.tile_width).ceil() as i32;
+        let max_z = ((object.y + object.height) / self.tile_height).ceil() as i32;
+
+        for x in min_x.max(0)..max_x.min(grid.length as i32) {
+            for z in min_z.max(0)..max_z.min(grid.width as i32) {
+                let loc = Location::new(x as i8, y, z as i8);
+                apply_properties(grid, &loc, &object.properties, schema)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl TiledLayer {
+    fn properties(&self) -> &Properties {
+        match self {
+            TiledLayer::Tiles { properties, .. } | TiledLayer::Objects { properties, .. } => {
+                properties
+            }
+        }
+    }
+}
+
+fn apply_properties(
+    grid: &mut Grid,
+    loc: &Location,
+    properties: &Properties,
+    schema: &TiledSchema,
+) -> Result<(), TiledError> {
+    let cell = grid.get_mut_cell(loc)?;
+
+    if let Some(Property::Bool(true)) = properties.get(&schema.obstructed) {
+        cell.is_obstructed = true;
+    }
+
+    if let Some(Property::Bool(true)) = properties.get(&schema.difficult_terrain) {
+        cell.terrain = Terrain::Difficult;
+    }
+
+    if let Some(Property::Bool(true)) = properties.get(&schema.water) {
+        cell.terrain = Terrain::Water;
+    }
+
+    if let Some(property) = properties.get(&schema.terrain) {
+        cell.terrain = to_terrain(&schema.terrain, property)?;
+    }
+
+    for (dir, name) in schema.walls.iter() {
+        let boundry = properties
+            .get(name)
+            .map(|property| to_boundry(name, property))
+            .transpose()?
+            .flatten();
+
+        if let Some(boundry) = boundry {
+            grid.add_cell_boundry_and_adjacent(loc, *dir, boundry)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn add_polyline_walls(
+    grid: &mut Grid,
+    y: i8,
+    from: (i32, i32),
+    to: (i32, i32),
+    boundry: Boundry,
+) -> Result<(), TiledError> {
+    if from.0 != to.0 && from.1 != to.1 {
+        return Err(TiledError::Unsupported(
+            "polyline walls must follow tile edges".to_string(),
+        ));
+    }
+
+    let (from_x, from_z) = (to_i8(from.0 as i64)?, to_i8(from.1 as i64)?);
+    let (to_x, to_z) = (to_i8(to.0 as i64)?, to_i8(to.1 as i64)?);
+
+    if from_z == to_z {
+        for x in from_x.min(to_x)..from_x.max(to_x) {
+            let loc = Location::new(x, y, from_z);
+            add_wall(grid, loc, Direction::N, boundry)?;
+        }
+    } else {
+        for z in from_z.min(to_z)..from_z.max(to_z) {
+            let loc = Location::new(from_x, y, z);
+            add_wall(grid, loc, Direction::W, boundry)?;
+        }
+    }
+
+    Ok(())
+}
+
+// Walls along the outside of the map have no cell on either side and are dropped.
+fn add_wall(
+    grid: &mut Grid,
+    loc: Location,
+    dir: Direction,
+    boundry: Boundry,
+) -> Result<(), TiledError> {
+    if grid.in_bounds(&loc) || grid.in_bounds(&loc.get_loc_from_dir(&dir)) {
+        grid.add_edge_boundry(loc, dir, boundry)?;
+    }
+
+    Ok(())
+}
+
+fn to_boundry(name: &str, property: &Property) -> Result<Option<Boundry>, TiledError> {
+    match property {
+        Property::Bool(false) => Ok(None),
+        Property::Bool(true) => Ok(Some(Boundry::Full)),
+        Property::Text(value) if value.eq_ignore_ascii_case("full") => Ok(Some(Boundry::Full)),
+        Property::Text(value) if value.eq_ignore_ascii_case("half") => Ok(Some(Boundry::Half)),
+        _ => Err(TiledError::Parse(format!(
+            "property {} must be \"full\" or \"half\"",
+            name
+        ))),
+    }
+}
+
+fn to_terrain(name: &str, property: &Property) -> Result<Terrain, TiledError> {
+    match property {
+        Property::Int(id) if (0..=u16::MAX as i64).contains(id) => Ok(Terrain::Custom(*id as u16)),
+        Property::Text(value) => match value.as_str() {
+            "floor" => Ok(Terrain::Floor),
+            "difficult" => Ok(Terrain::Difficult),
+            "water" => Ok(Terrain::Water),
+            "deep_water" => Ok(Terrain::DeepWater),
+            "lava" => Ok(Terrain::Lava),
+            "ice" => Ok(Terrain::Ice),
+            "rubble" => Ok(Terrain::Rubble),
+            "vegetation" => Ok(Terrain::Vegetation),
+            _ => Err(TiledError::Parse(format!(
+                "property {} names an unknown terrain {}",
+                name, value
+            ))),
+        },
+        _ => Err(TiledError::Parse(format!(
+            "property {} must name a terrain",
+            name
+        ))),
+    }
+}
+
+fn to_i8(value: i64) -> Result<i8, TiledError> {
+    if value < 0 || value > i8::MAX as i64 {
+        return Err(TiledError::Unsupported(format!(
+            "{} does not fit in the grid",
+            value
+        )));
+    }
+
+    Ok(value as i8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    const JSON: &str = r#"{
+        "width": 3, "height": 2, "tilewidth": 32, "tileheight": 32, "infinite": false,
+        "tilesets": [{
+            "firstgid": 1,
+            "tiles": [
+                {"id": 1, "properties": [{"name": "obstructed", "type": "bool", "value": true}]},
+                {"id": 2, "properties": [{"name": "difficult_terrain", "type": "bool", "value": true}]},
+                {"id": 3, "properties": [{"name": "wall_e", "type": "string", "value": "full"}]}
+            ]
+        }],
+        "layers": [
+            {"type": "tilelayer", "data": [1, 2, 1, 4, 3, 1]},
+            {"type": "objectgroup", "objects": [
+                {"x": 64, "y": 0, "width": 32, "height": 32,
+                 "properties": [{"name": "difficult_terrain", "type": "bool", "value": true}]},
+                {"x": 32, "y": 0, "polyline": [{"x": 0, "y": 0}, {"x": 0, "y": 32}],
+                 "properties": [{"name": "wall", "type": "string", "value": "half"}]}
+            ]},
+            {"type": "group", "layers": [
+                {"type": "tilelayer", "data": [0, 0, 0, 0, 0, 2]}
+            ]}
+        ]
+    }"#;
+
+    const TMX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map width="3" height="2" tilewidth="32" tileheight="32" infinite="0">
+ <tileset firstgid="1" name="dungeon">
+  <tile id="1"><properties><property name="obstructed" type="bool" value="true"/></properties></tile>
+  <tile id="2"><properties><property name="difficult_terrain" type="bool" value="true"/></properties></tile>
+  <tile id="3"><properties><property name="wall_e" value="full"/></properties></tile>
+ </tileset>
+ <layer name="ground" width="3" height="2">
+  <data encoding="csv">
+1,2,1,
+4,3,1
+</data>
+ </layer>
+ <objectgroup name="markers">
+  <object x="64" y="0" width="32" height="32">
+   <properties><property name="difficult_terrain" type="bool" value="true"/></properties>
+  </object>
+  <object x="32" y="0">
+   <properties><property name="wall" value="half"/></properties>
+   <polyline points="0,0 0,32"/>
+  </object>
+ </objectgroup>
+ <group name="upstairs">
+  <layer name="roof" width="3" height="2">
+   <data>
+    <tile/><tile/><tile/><tile/><tile/><tile gid="2"/>
+   </data>
+  </layer>
+ </group>
+</map>"#;
+
+    const EXPECTED: &str = "\
++ + + +
+ .!# ~
++ + + +
+ .|~ .
++ + + +
+
++ + + +
+ . . .
++ + + +
+ . . #
++ + + +
+";
+
+    #[test]
+    fn imports_tiled_json() {
+        let grid = Grid::from_tiled_json(JSON, &TiledSchema::default()).unwrap();
+        assert_eq!(grid, Grid::from_ascii(EXPECTED).unwrap());
+    }
+
+    #[test]
+    fn imports_tmx() {
+        let grid = Grid::from_tmx(TMX, &TiledSchema::default()).unwrap();
+        assert_eq!(grid, Grid::from_ascii(EXPECTED).unwrap());
+    }
+
+    #[test]
+    fn schema_renames_properties() {
+        let schema = TiledSchema {
+            obstructed: "solid".to_string(),
+            ..TiledSchema::default()
+        };
+
+        let grid = Grid::from_tiled_json(JSON, &schema).unwrap();
+        assert!(
+            !grid
+                .get_ref_cell(&Location::new(1, 0, 0))
+                .unwrap()
+                .is_obstructed
+        );
+    }
+
+    #[rstest]
+    #[case("\"type\": \"string\", \"value\": \"lava\"", Ok(Terrain::Lava))]
+    #[case("\"type\": \"int\", \"value\": 12", Ok(Terrain::Custom(12)))]
+    #[case("\"type\": \"string\", \"value\": \"mud\"", Err(()))]
+    fn terrain_property(#[case] property: &str, #[case] expected: Result<Terrain, ()>) {
+        let text = JSON.replace(
+            "\"name\": \"difficult_terrain\", \"type\": \"bool\", \"value\": true",
+            &format!("\"name\": \"terrain\", {}", property),
+        );
+
+        let terrain = Grid::from_tiled_json(&text, &TiledSchema::default())
+            .map(|grid| grid.get_ref_cell(&Location::new(1, 0, 1)).unwrap().terrain)
+            .map_err(|_| ());
+
+        assert_eq!(terrain, expected);
+    }
+
+    #[rstest]
+    #[case(JSON.replace("\"infinite\": false", "\"infinite\": true"))]
+    #[case(JSON.replace("\"firstgid\": 1,", "\"firstgid\": 1, \"source\": \"dungeon.tsj\","))]
+    #[case(JSON.replace("\"type\": \"tilelayer\",", "\"type\": \"tilelayer\", \"encoding\": \"base64\","))]
+    #[case(JSON.replace("{\"x\": 0, \"y\": 32}", "{\"x\": 0, \"y\": 1e12}"))]
+    #[case(JSON.replace("{\"x\": 0, \"y\": 0}, {\"x\": 0", "{\"x\": 4096, \"y\": 0}, {\"x\": 4096"))]
+    fn unsupported_json(#[case] text: String) {
+        assert!(matches!(
+            Grid::from_tiled_json(&text, &TiledSchema::default()),
+            Err(TiledError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn tile_ids_past_the_last_gid_are_errors() {
+        let json = JSON.replace("\"firstgid\": 1", "\"firstgid\": 4294967295");
+        let tmx = TMX.replace("firstgid=\"1\"", "firstgid=\"4294967295\"");
+
+        assert!(matches!(
+            Grid::from_tiled_json(&json, &TiledSchema::default()),
+            Err(TiledError::Parse(_))
+        ));
+        assert!(matches!(
+            Grid::from_tmx(&tmx, &TiledSchema::default()),
+            Err(TiledError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn short_tile_layer_is_an_error() {
+        let text = JSON.replace("[1, 2, 1, 4, 3, 1]", "[1, 2, 1]");
+
+        assert!(matches!(
+            Grid::from_tiled_json(&text, &TiledSchema::default()),
+            Err(TiledError::Parse(_))
+        ));
+    }
+}
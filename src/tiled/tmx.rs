@@ -0,0 +1,180 @@
+use super::{Properties, Property, TiledError, TiledLayer, TiledMap, TiledObject};
+use roxmltree::{Document, Node};
+use std::collections::HashMap;
+
+pub(super) fn parse(text: &str) -> Result<TiledMap, TiledError> {
+    let document = Document::parse(text).map_err(|err| TiledError::Parse(err.to_string()))?;
+    let map = document.root_element();
+
+    if !map.has_tag_name("map") {
+        return Err(TiledError::Parse("missing map element".to_string()));
+    }
+
+    if map.attribute("infinite") == Some("1") {
+        return Err(TiledError::Unsupported("infinite maps".to_string()));
+    }
+
+    let mut tiles = HashMap::new();
+
+    for tileset in children(map, "tileset") {
+        if tileset.has_attribute("source") {
+            return Err(TiledError::Unsupported("external tilesets".to_string()));
+        }
+
+        let first_gid: u32 = attribute(tileset, "firstgid")?;
+
+        for tile in children(tileset, "tile") {
+            let gid = first_gid
+                .checked_add(attribute(tile, "id")?)
+                .ok_or_else(|| TiledError::Parse("tile id out of range".to_string()))?;
+            tiles.insert(gid, properties(tile)?);
+        }
+    }
+
+    let mut layers = vec![];
+    parse_layers(map, &mut layers)?;
+
+    Ok(TiledMap {
+        width: attribute(map, "width")?,
+        height: attribute(map, "height")?,
+        tile_width: attribute(map, "tilewidth")?,
+        tile_height: attribute(map, "tileheight")?,
+        tiles,
+        layers,
+    })
+}
+
+// Group layers are flattened, keeping the order their layers are drawn in.
+fn parse_layers(parent: Node, layers: &mut Vec<TiledLayer>) -> Result<(), TiledError> {
+    for layer in parent.children().filter(|node| node.is_element()) {
+        match layer.tag_name().name() {
+            "layer" => {
+                let data = children(layer, "data")
+                    .next()
+                    .ok_or_else(|| TiledError::Parse("tile layer without data".to_string()))?;
+
+                let data = match data.attribute("encoding") {
+                    Some("csv") => data
+                        .text()
+                        .unwrap_or("")
+                        .split(',')
+                        .map(|gid| parse_value::<u32>("gid", gid.trim()))
+                        .collect::<Result<Vec<u32>, TiledError>>()?,
+                    None => children(data, "tile")
+                        .map(|tile| {
+                            tile.attribute("gid")
+                                .map_or(Ok(0), |gid| parse_value("gid", gid))
+                        })
+                        .collect::<Result<Vec<u32>, TiledError>>()?,
+                    Some(_) => {
+                        return Err(TiledError::Unsupported(
+                            "tile layer encodings other than csv".to_string(),
+                        ))
+                    }
+                };
+
+                layers.push(TiledLayer::Tiles {
+                    properties: properties(layer)?,
+                    data,
+                });
+            }
+            "objectgroup" => {
+                let objects = children(layer, "object")
+                    .map(object)
+                    .collect::<Result<Vec<TiledObject>, TiledError>>()?;
+
+                layers.push(TiledLayer::Objects {
+                    properties: properties(layer)?,
+                    objects,
+                });
+            }
+            "group" => parse_layers(layer, layers)?,
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn object(node: Node) -> Result<TiledObject, TiledError> {
+    let polyline = match children(node, "polyline").next() {
+        Some(polyline) => Some(
+            polyline
+                .attribute("points")
+                .unwrap_or("")
+                .split_whitespace()
+                .map(|point| {
+                    let mut coords = point.split(',');
+
+                    match (coords.next(), coords.next(), coords.next()) {
+                        (Some(x), Some(y), None) => {
+                            Ok((parse_value("point", x)?, parse_value("point", y)?))
+                        }
+                        _ => Err(TiledError::Parse(format!("invalid point {}", point))),
+                    }
+                })
+                .collect::<Result<Vec<(f32, f32)>, TiledError>>()?,
+        ),
+        None => None,
+    };
+
+    Ok(TiledObject {
+        x: attribute(node, "x")?,
+        y: attribute(node, "y")?,
+        width: node
+            .attribute("width")
+            .map_or(Ok(0.0), |width| parse_value("width", width))?,
+        height: node
+            .attribute("height")
+            .map_or(Ok(0.0), |height| parse_value("height", height))?,
+        polyline,
+        properties: properties(node)?,
+    })
+}
+
+fn properties(node: Node) -> Result<Properties, TiledError> {
+    let mut properties = HashMap::new();
+
+    for property in children(node, "properties").flat_map(|node| children(node, "property")) {
+        let name = property
+            .attribute("name")
+            .ok_or_else(|| TiledError::Parse("property without a name".to_string()))?;
+
+        // Multi-line string values are stored as the element's text.
+        let value = property
+            .attribute("value")
+            .or_else(|| property.text())
+            .unwrap_or("");
+
+        let value = match property.attribute("type") {
+            Some("bool") => Property::Bool(parse_value(name, value)?),
+            Some("int") => Property::Int(parse_value(name, value)?),
+            _ => Property::Text(value.to_string()),
+        };
+
+        properties.insert(name.to_string(), value);
+    }
+
+    Ok(properties)
+}
+
+fn children<'a, 'input: 'a>(
+    node: Node<'a, 'input>,
+    tag: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    node.children().filter(move |child| child.has_tag_name(tag))
+}
+
+fn attribute<T: std::str::FromStr>(node: Node, name: &str) -> Result<T, TiledError> {
+    let value = node
+        .attribute(name)
+        .ok_or_else(|| TiledError::Parse(format!("missing attribute {}", name)))?;
+
+    parse_value(name, value)
+}
+
+fn parse_value<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, TiledError> {
+    value
+        .parse()
+        .map_err(|_| TiledError::Parse(format!("invalid value {} for {}", value, name)))
+}
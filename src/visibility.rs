@@ -0,0 +1,506 @@
+use crate::prelude::{Boundry, Grid, GridError, Location};
+use std::collections::HashSet;
+
+impl Grid {
+    // Walks a 3D supercover line between the centers of `from` and `to`. When the
+    // line passes exactly through an edge or corner, every cell touching it is
+    // crossed and sight is kept if any of the routes around that corner is clear.
+    pub fn has_line_of_sight(
+        &self,
+        from: &Location,
+        to: &Location,
+    ) -> Result<(bool, Vec<Location>), GridError> {
+        self.get_ref_cell(from)?;
+        self.get_ref_cell(to)?;
+
+        let start = [from.x, from.y, from.z];
+        let deltas = [to.x - from.x, to.y - from.y, to.z - from.z];
+        let steps = [deltas[0].abs(), deltas[1].abs(), deltas[2].abs()];
+        let signs = [deltas[0].signum(), deltas[1].signum(), deltas[2].signum()];
+
+        let mut taken = [0i8; 3];
+        let mut current = *from;
+        let mut cells = vec![current];
+
+        while taken != steps {
+            let axes = next_axes(&taken, &steps);
+
+            let mut crossed = vec![];
+            let is_clear = self.is_sight_step_clear(&current, &axes, &signs, to, &mut crossed);
+
+            for axis in axes.iter() {
+                taken[*axis] += 1;
+            }
+            current = Location::new(
+                start[0] + taken[0] * signs[0],
+                start[1] + taken[1] * signs[1],
+                start[2] + taken[2] * signs[2],
+            );
+
+            crossed.retain(|loc| *loc != current);
+            crossed.sort();
+            crossed.dedup();
+            cells.append(&mut crossed);
+            cells.push(current);
+
+            if !is_clear {
+                return Ok((false, cells));
+            }
+        }
+
+        Ok((true, cells))
+    }
+
+    // Symmetric shadowcasting on the origin's layer. The grid is scanned at double
+    // resolution, where odd coordinates are cells and even coordinates are the
+    // edges and corners between them, so that boundries occlude like any tile.
+    pub fn field_of_view(
+        &self,
+        origin: &Location,
+        radius: i8,
+    ) -> Result<HashSet<Location>, GridError> {
+        self.get_ref_cell(origin)?;
+
+        let mut visible = HashSet::new();
+        visible.insert(*origin);
+
+        let expanded_origin = (2 * origin.x as i32 + 1, 2 * origin.z as i32 + 1);
+        let max_depth = 2 * radius as i32 + 1;
+
+        for quadrant in [
+            Quadrant::North,
+            Quadrant::East,
+            Quadrant::South,
+            Quadrant::West,
+        ]
+        .iter()
+        {
+            let scan = ShadowScan {
+                origin,
+                expanded_origin,
+                quadrant,
+                radius,
+                max_depth,
+            };
+            self.scan_row(&scan, Row::new(1, Slope(-1, 1), Slope(1, 1)), &mut visible);
+        }
+
+        Ok(visible)
+    }
+
+    fn scan_row(&self, scan: &ShadowScan, mut row: Row, visible: &mut HashSet<Location>) {
+        if row.depth > scan.max_depth {
+            return;
+        }
+
+        let mut prev_is_opaque = None;
+
+        for col in row.min_col()..=row.max_col() {
+            let (x, z) = scan
+                .quadrant
+                .transform(scan.expanded_origin, row.depth, col);
+            let is_opaque = self.is_expanded_tile_opaque(scan.origin.y, x, z);
+
+            if is_opaque {
+                self.reveal_opaque_tile(scan, x, z, visible);
+            } else if row.is_symmetric(col) {
+                scan.reveal(x, z, visible);
+            }
+
+            if prev_is_opaque == Some(true) && !is_opaque {
+                row.start_slope = Slope::of_tile(row.depth, col);
+            }
+
+            if prev_is_opaque == Some(false) && is_opaque {
+                let mut next_row = row.next();
+                next_row.end_slope = Slope::of_tile(row.depth, col);
+                self.scan_row(scan, next_row, visible);
+            }
+
+            prev_is_opaque = Some(is_opaque);
+        }
+
+        if prev_is_opaque == Some(false) {
+            self.scan_row(scan, row.next(), visible);
+        }
+    }
+
+    fn is_expanded_tile_opaque(&self, y: i8, x: i32, z: i32) -> bool {
+        if x < 0 || z < 0 || x > 2 * self.length as i32 || z > 2 * self.width as i32 {
+            return true;
+        }
+
+        match (x % 2 == 1, z % 2 == 1) {
+            (true, true) => self.is_cell_opaque(&Location::new((x / 2) as i8, y, (z / 2) as i8)),
+            (true, false) | (false, true) => {
+                let (current_loc, new_loc) = edge_cells(y, x, z);
+                self.is_edge_opaque(&current_loc, &new_loc)
+            }
+            (false, false) => {
+                let (x, z) = ((x / 2) as i8, (z / 2) as i8);
+                let north_west = Location::new(x - 1, y, z - 1);
+                let north_east = Location::new(x, y, z - 1);
+                let south_west = Location::new(x - 1, y, z);
+                let south_east = Location::new(x, y, z);
+
+                self.is_edge_opaque(&north_west, &north_east)
+                    || self.is_edge_opaque(&south_west, &south_east)
+                    || self.is_edge_opaque(&north_west, &south_west)
+                    || self.is_edge_opaque(&north_east, &south_east)
+                    || self.is_edge_opaque(&north_west, &south_east)
+                    || self.is_edge_opaque(&north_east, &south_west)
+            }
+        }
+    }
+
+    // Obstructed cells sit behind their own opaque edges, so they are revealed
+    // when one of those edges is, unless a wall stands in front of them.
+    fn reveal_opaque_tile(
+        &self,
+        scan: &ShadowScan,
+        x: i32,
+        z: i32,
+        visible: &mut HashSet<Location>,
+    ) {
+        match (x % 2 == 1, z % 2 == 1) {
+            (true, true) => scan.reveal(x, z, visible),
+            (true, false) | (false, true) => {
+                let (current_loc, new_loc) = edge_cells(scan.origin.y, x, z);
+
+                if self.boundry_between(&current_loc, &new_loc) == Ok(Some(Boundry::Full)) {
+                    return;
+                }
+
+                for loc in [current_loc, new_loc].iter() {
+                    if self.get_ref_cell(loc).is_ok_and(|cell| cell.is_obstructed) {
+                        scan.reveal(2 * loc.x as i32 + 1, 2 * loc.z as i32 + 1, visible);
+                    }
+                }
+            }
+            (false, false) => {}
+        }
+    }
+
+    fn is_cell_opaque(&self, loc: &Location) -> bool {
+        self.get_ref_cell(loc)
+            .map_or(true, |cell| cell.is_obstructed)
+    }
+
+    fn is_edge_opaque(&self, current_loc: &Location, new_loc: &Location) -> bool {
+        self.is_cell_opaque(current_loc)
+            || self.is_cell_opaque(new_loc)
+            || self.boundry_between(current_loc, new_loc) == Ok(Some(Boundry::Full))
+    }
+
+    fn is_sight_step_clear(
+        &self,
+        current: &Location,
+        axes: &[usize],
+        signs: &[i8; 3],
+        target: &Location,
+        crossed: &mut Vec<Location>,
+    ) -> bool {
+        if axes.len() > 1 {
+            let diagonal = axes
+                .iter()
+                .fold(*current, |loc, axis| step_axis(&loc, *axis, signs[*axis]));
+
+            if let Ok(Some(Boundry::Full)) = self.boundry_between(current, &diagonal) {
+                return false;
+            }
+        }
+
+        let mut is_clear = axes.is_empty();
+
+        for axis in axes.iter() {
+            let next = step_axis(current, *axis, signs[*axis]);
+            crossed.push(next);
+
+            if self.blocks_sight(current, &next, target) {
+                continue;
+            }
+
+            let rest: Vec<usize> = axes.iter().copied().filter(|a| a != axis).collect();
+
+            if self.is_sight_step_clear(&next, &rest, signs, target, crossed) {
+                is_clear = true;
+            }
+        }
+
+        is_clear
+    }
+
+    fn blocks_sight(&self, current: &Location, next: &Location, target: &Location) -> bool {
+        if let Ok(Some(Boundry::Full)) = self.boundry_between(current, next) {
+            return true;
+        }
+
+        next != target
+            && self
+                .get_ref_cell(next)
+                .map_or(true, |cell| cell.is_obstructed)
+    }
+}
+
+fn edge_cells(y: i8, x: i32, z: i32) -> (Location, Location) {
+    if x % 2 == 0 {
+        let z = (z / 2) as i8;
+        (
+            Location::new((x / 2 - 1) as i8, y, z),
+            Location::new((x / 2) as i8, y, z),
+        )
+    } else {
+        let x = (x / 2) as i8;
+        (
+            Location::new(x, y, (z / 2 - 1) as i8),
+            Location::new(x, y, (z / 2) as i8),
+        )
+    }
+}
+
+struct ShadowScan<'a> {
+    origin: &'a Location,
+    expanded_origin: (i32, i32),
+    quadrant: &'a Quadrant,
+    radius: i8,
+    max_depth: i32,
+}
+
+impl ShadowScan<'_> {
+    fn reveal(&self, x: i32, z: i32, visible: &mut HashSet<Location>) {
+        if x % 2 == 0 || z % 2 == 0 {
+            return;
+        }
+
+        let loc = Location::new((x / 2) as i8, self.origin.y, (z / 2) as i8);
+        let (dx, dz) = (
+            (loc.x - self.origin.x) as i32,
+            (loc.z - self.origin.z) as i32,
+        );
+
+        if dx * dx + dz * dz <= (self.radius as i32) * (self.radius as i32) {
+            visible.insert(loc);
+        }
+    }
+}
+
+enum Quadrant {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Quadrant {
+    fn transform(&self, origin: (i32, i32), depth: i32, col: i32) -> (i32, i32) {
+        match *self {
+            Quadrant::North => (origin.0 + col, origin.1 - depth),
+            Quadrant::South => (origin.0 + col, origin.1 + depth),
+            Quadrant::East => (origin.0 + depth, origin.1 + col),
+            Quadrant::West => (origin.0 - depth, origin.1 + col),
+        }
+    }
+}
+
+// A rational slope, numerator over a positive denominator.
+#[derive(Clone, Copy)]
+struct Slope(i32, i32);
+
+impl Slope {
+    fn of_tile(depth: i32, col: i32) -> Self {
+        Slope(2 * col - 1, 2 * depth)
+    }
+}
+
+struct Row {
+    depth: i32,
+    start_slope: Slope,
+    end_slope: Slope,
+}
+
+impl Row {
+    fn new(depth: i32, start_slope: Slope, end_slope: Slope) -> Self {
+        Row {
+            depth,
+            start_slope,
+            end_slope,
+        }
+    }
+
+    fn min_col(&self) -> i32 {
+        let Slope(num, den) = self.start_slope;
+        (2 * self.depth * num + den).div_euclid(2 * den)
+    }
+
+    fn max_col(&self) -> i32 {
+        let Slope(num, den) = self.end_slope;
+        -(den - 2 * self.depth * num).div_euclid(2 * den)
+    }
+
+    fn is_symmetric(&self, col: i32) -> bool {
+        let Slope(start_num, start_den) = self.start_slope;
+        let Slope(end_num, end_den) = self.end_slope;
+
+        col * start_den >= self.depth * start_num && col * end_den <= self.depth * end_num
+    }
+
+    fn next(&self) -> Self {
+        Row::new(self.depth + 1, self.start_slope, self.end_slope)
+    }
+}
+
+fn step_axis(loc: &Location, axis: usize, sign: i8) -> Location {
+    match axis {
+        0 => Location::new(loc.x + sign, loc.y, loc.z),
+        1 => Location::new(loc.x, loc.y + sign, loc.z),
+        _ => Location::new(loc.x, loc.y, loc.z + sign),
+    }
+}
+
+// The line leaves the current cell along axis `a` at t = (2 * taken + 1) / (2 * steps),
+// so the axes crossed next are the ones with the smallest such boundary.
+fn next_axes(taken: &[i8; 3], steps: &[i8; 3]) -> Vec<usize> {
+    let boundary = |axis: usize| (2 * taken[axis] as i32 + 1, steps[axis] as i32);
+
+    let mut axes: Vec<usize> = vec![];
+
+    for axis in (0..3).filter(|axis| taken[*axis] < steps[*axis]) {
+        let (num, den) = boundary(axis);
+
+        match axes.first().map(|first| boundary(*first)) {
+            Some((best_num, best_den)) if num * best_den > best_num * den => {}
+            Some((best_num, best_den)) if num * best_den == best_num * den => axes.push(axis),
+            _ => axes = vec![axis],
+        }
+    }
+
+    axes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::Direction;
+    use rstest::{fixture, rstest};
+
+    #[fixture]
+    fn grid() -> Grid {
+        let mut grid = Grid::new(7, 6, 2);
+        grid.add_cell_boundry_and_adjacent(&Location::new(2, 0, 1), Direction::E, Boundry::Full)
+            .unwrap();
+        grid.add_cell_boundry_and_adjacent(&Location::new(2, 0, 3), Direction::E, Boundry::Half)
+            .unwrap();
+        grid.add_cell_boundry_and_adjacent(&Location::new(4, 0, 4), Direction::U, Boundry::Full)
+            .unwrap();
+
+        grid.toggle_cell_obstruction(&Location::new(1, 0, 5))
+            .unwrap();
+
+        grid
+    }
+
+    #[rstest]
+    #[case(&Location::new(0, 0, 1), &Location::new(5, 0, 1), Ok(false))]
+    #[case(&Location::new(0, 0, 3), &Location::new(5, 0, 3), Ok(true))]
+    #[case(&Location::new(0, 0, 5), &Location::new(3, 0, 5), Ok(false))]
+    #[case(&Location::new(0, 0, 5), &Location::new(1, 0, 5), Ok(true))]
+    #[case(&Location::new(0, 0, 4), &Location::new(2, 0, 2), Ok(true))]
+    #[case(&Location::new(4, 0, 4), &Location::new(4, 1, 4), Ok(false))]
+    #[case(&Location::new(3, 0, 4), &Location::new(5, 1, 4), Ok(false))]
+    #[case(&Location::new(3, 0, 3), &Location::new(5, 1, 3), Ok(true))]
+    #[case(&Location::new(0, 0, 0), &Location::new(7, 0, 0), Err(GridError::OutOfBounds(Location::new(7, 0, 0))))]
+    fn line_of_sight_tests(
+        grid: Grid,
+        #[case] from: &Location,
+        #[case] to: &Location,
+        #[case] expected: Result<bool, GridError>,
+    ) {
+        let result = grid
+            .has_line_of_sight(from, to)
+            .map(|(is_clear, _)| is_clear);
+        assert_eq!(result, expected);
+    }
+
+    #[fixture]
+    fn room() -> Grid {
+        let mut grid = Grid::new(9, 9, 1);
+        grid.add_cell_boundry_and_adjacent(&Location::new(5, 0, 4), Direction::E, Boundry::Full)
+            .unwrap();
+        grid.add_cell_boundry_and_adjacent(&Location::new(4, 0, 2), Direction::N, Boundry::Half)
+            .unwrap();
+        grid.add_cell_boundry_and_adjacent(&Location::new(4, 0, 6), Direction::S, Boundry::Full)
+            .unwrap();
+
+        grid.toggle_cell_obstruction(&Location::new(2, 0, 4))
+            .unwrap();
+
+        grid
+    }
+
+    #[rstest]
+    #[case(&Location::new(5, 0, 4), true)]
+    #[case(&Location::new(6, 0, 4), false)]
+    #[case(&Location::new(7, 0, 4), false)]
+    #[case(&Location::new(4, 0, 1), true)]
+    #[case(&Location::new(4, 0, 0), true)]
+    #[case(&Location::new(2, 0, 4), true)]
+    #[case(&Location::new(1, 0, 4), false)]
+    #[case(&Location::new(4, 0, 6), true)]
+    #[case(&Location::new(4, 0, 7), false)]
+    #[case(&Location::new(6, 0, 6), true)]
+    #[case(&Location::new(7, 0, 7), false)]
+    fn field_of_view_tests(room: Grid, #[case] loc: &Location, #[case] expected: bool) {
+        let result = room.field_of_view(&Location::new(4, 0, 4), 4).unwrap();
+        assert_eq!(result.contains(loc), expected);
+    }
+
+    #[rstest]
+    fn field_of_view_is_symmetric(room: Grid) {
+        let origin = Location::new(4, 0, 4);
+        let visible = room.field_of_view(&origin, 4).unwrap();
+
+        for loc in visible
+            .iter()
+            .filter(|loc| !room.get_ref_cell(loc).unwrap().is_obstructed)
+        {
+            assert!(room.field_of_view(loc, 4).unwrap().contains(&origin));
+        }
+    }
+
+    #[rstest]
+    fn line_of_sight_crosses_both_cells_at_a_corner(grid: Grid) {
+        let (_, cells) = grid
+            .has_line_of_sight(&Location::new(0, 0, 0), &Location::new(2, 0, 2))
+            .unwrap();
+
+        assert_eq!(
+            cells,
+            vec![
+                Location::new(0, 0, 0),
+                Location::new(0, 0, 1),
+                Location::new(1, 0, 0),
+                Location::new(1, 0, 1),
+                Location::new(1, 0, 2),
+                Location::new(2, 0, 1),
+                Location::new(2, 0, 2),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn line_of_sight_stops_at_blocking_wall(grid: Grid) {
+        let (result, cells) = grid
+            .has_line_of_sight(&Location::new(1, 0, 1), &Location::new(4, 0, 1))
+            .unwrap();
+
+        assert!(!result);
+        assert_eq!(
+            cells,
+            vec![
+                Location::new(1, 0, 1),
+                Location::new(2, 0, 1),
+                Location::new(3, 0, 1)
+            ]
+        );
+    }
+}